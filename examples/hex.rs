@@ -0,0 +1,337 @@
+use winit::event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
+use winit::window::WindowId;
+
+use vulkan_hex::debug_draw::{self, Style};
+use vulkan_hex::gui::panels;
+use vulkan_hex::model::{BlendMode, GameObject, Material, Mesh};
+use vulkan_hex::postprocess::{self, CustomEffect, PostEffect};
+use vulkan_hex::render::{HdrMode, PresentModeConfig, RenderConfig, SwapchainConfig, SCENE_PASS};
+use vulkan_hex::shadow::ShadowConfig;
+use vulkan_hex::simple_display::CullStats;
+use vulkan_hex::text::{self, Align, Font, TextConfig, TextStyle};
+use vulkan_hex::window::{FullscreenMode, WindowConfig};
+use vulkan_hex::{egui, App, AppConfig, Engine, Frame, Result};
+
+const RENDER_CONFIG: RenderConfig = RenderConfig {
+    samples: 4,
+    stencil: false,
+    reverse_z: true,
+    frames_in_flight: 2,
+    swapchain: SwapchainConfig {
+        present_mode: PresentModeConfig::Vsync,
+        image_count: None,
+        hdr: None,
+    },
+};
+
+/// Field of view of the first window, later windows get a wider map view.
+const FOV_Y: f32 = std::f32::consts::PI * (50.0 / 90.0);
+const MAP_FOV_Y: f32 = std::f32::consts::PI * (70.0 / 90.0);
+
+/// Blends towards gray by `params.x`, registered as a custom post effect.
+mod desaturate_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+                #version 450
+
+                layout(location = 0) in vec2 uv;
+                layout(location = 0) out vec4 f_color;
+
+                layout(set = 0, binding = 0) uniform texture2D source;
+                layout(set = 0, binding = 1) uniform sampler source_sampler;
+
+                layout(push_constant) uniform Params {
+                    vec4 params;
+                    vec2 texel_size;
+                    uint output_transfer;
+                } push;
+
+                void main() {
+                    vec3 color = texture(sampler2D(source, source_sampler), uv).rgb;
+                    float gray = dot(color, vec3(0.2126, 0.7152, 0.0722));
+                    f_color = vec4(mix(color, vec3(gray), push.params.x), 1.0);
+                }
+            "
+    }
+}
+
+fn get_desaturate_effect(amount: f32) -> PostEffect {
+    PostEffect::Custom(CustomEffect::new(
+        "desaturate",
+        [amount, 0.0, 0.0, 0.0],
+        |device, render_pass| {
+            let fs = desaturate_fs::Shader::load(device.clone())?;
+            postprocess::build_pipeline(device, render_pass, fs.main_entry_point(), "desaturate")
+        },
+    ))
+}
+
+/// Reads the optional HDR output mode from `HEX_HDR` (`hdr10` or `scrgb`).
+fn get_hdr_mode() -> Option<HdrMode> {
+    match std::env::var("HEX_HDR").as_deref() {
+        Ok("hdr10") => Some(HdrMode::Hdr10),
+        Ok("scrgb") => Some(HdrMode::ScRgb),
+        _ => None,
+    }
+}
+
+/// Reads the overlay font from `HEX_FONT`, falling back to DejaVu Sans.
+fn get_text_config() -> Option<TextConfig> {
+    let path = std::env::var("HEX_FONT")
+        .unwrap_or_else(|_| "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf".to_owned());
+    match Font::from_file(&path) {
+        Ok(font) => Some(TextConfig::new(font)),
+        Err(e) => {
+            log::warn!("no text overlay, {}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Three spinning cubes behind a translucent one, `V` cycles the present mode,
+/// `N` opens another window and `B` toggles their bounds and labels. The
+/// objects can be edited in the UI.
+#[derive(Default)]
+struct Hex {
+    game_objects: Vec<GameObject>,
+    cull_stats: CullStats,
+    show_bounds: bool,
+    last_update: Option<std::time::Instant>,
+    /// Smoothed frames per second.
+    fps: f32,
+}
+
+impl App for Hex {
+    fn init(&mut self, engine: &mut Engine) -> Result<()> {
+        let logical_device = &engine.logical_device;
+        let features = logical_device.enabled_features();
+        log::debug!(
+            "anisotropy: {}, wide lines: {}, multi draw indirect: {}",
+            features.sampler_anisotropy,
+            features.wide_lines,
+            features.multi_draw_indirect
+        );
+        log::debug!("extensions: {:?}", logical_device.enabled_extensions());
+        log::debug!(
+            "queue families: graphical {}, present {}, compute {}, transfer {}",
+            logical_device.graphical_queue.family().id(),
+            logical_device.present_queue.family().id(),
+            logical_device.compute_queue.family().id(),
+            logical_device.transfer_queue.family().id()
+        );
+        for view in engine.views.values() {
+            log::info!("surface format: {:?}", view.render.get_surface_format());
+        }
+
+        let cube = Mesh::cube(&logical_device.device)?;
+        let material = Material::new();
+        let glass = Material::with_blend(BlendMode::Alpha);
+        let mut highlight = GameObject::new(
+            &cube,
+            &glass,
+            [0.0, 0.0, 2.0],
+            [1.5, 1.5, 1.5],
+            [0.0, 0.0, 0.0],
+        );
+        highlight.tint = [0.4, 0.8, 1.0];
+        highlight.alpha = 0.35;
+        self.game_objects = vec![
+            GameObject::new(
+                &cube,
+                &material,
+                [0.0, 0.0, 0.5],
+                [0.1, 0.5, 0.1],
+                [0.0, 0.0, 0.0],
+            ),
+            GameObject::new(
+                &cube,
+                &material,
+                [0.0, -10.0, 10.0],
+                [0.5, 1.0, 0.5],
+                [std::f32::consts::PI, 0.0, 0.0],
+            ),
+            GameObject::new(
+                &cube,
+                &material,
+                [5.0, 10.0, 15.0],
+                [0.5, 0.5, 0.5],
+                [2.0 * std::f32::consts::PI, 0.0, 0.0],
+            ),
+            highlight,
+        ];
+        Ok(())
+    }
+
+    fn update(&mut self, _engine: &mut Engine) -> Result<()> {
+        let now = std::time::Instant::now();
+        if let Some(last_update) = self.last_update.replace(now) {
+            let fps = 1.0 / (now - last_update).as_secs_f32().max(1e-4);
+            self.fps += (fps - self.fps) * 0.05;
+        }
+        text::screen(
+            &format!(
+                "{:.0} fps\ndrawn: {}, culled: {}",
+                self.fps, self.cull_stats.drawn, self.cull_stats.culled
+            ),
+            glam::Vec2::new(8.0, 8.0),
+            TextStyle::default(),
+        );
+        for objs in self.game_objects.iter_mut() {
+            objs.rotate[0] += 0.01;
+            objs.rotate[1] += 0.01;
+            objs.rotate[2] += 0.01;
+        }
+        if self.show_bounds {
+            for (index, obj) in self.game_objects.iter().enumerate() {
+                let (aabb, sphere) = obj.get_world_bounds();
+                debug_draw::aabb(&aabb, Style::color([1.0, 1.0, 0.0, 1.0]));
+                debug_draw::sphere(
+                    sphere.center,
+                    sphere.radius,
+                    Style::color([0.0, 1.0, 1.0, 0.5]),
+                );
+                let overlay = Style {
+                    depth_test: false,
+                    ..Style::default()
+                };
+                debug_draw::axes(&obj.get_model_matrix(), 1.0, overlay);
+                text::world(
+                    &format!("object {}", index),
+                    aabb.center(),
+                    TextStyle {
+                        color: [1.0, 1.0, 0.0, 1.0],
+                        align: Align::Center,
+                        ..TextStyle::default()
+                    },
+                );
+            }
+            debug_draw::hex_outline(glam::Vec3::new(0.0, 2.0, 8.0), 3.0, Style::default());
+        }
+        Ok(())
+    }
+
+    fn render(&mut self, frame: &mut Frame) -> Result<()> {
+        let stats = frame.draw_game_objects(&self.game_objects)?;
+        if frame.pass == SCENE_PASS && stats != self.cull_stats {
+            log::debug!("drawn: {}, culled: {}", stats.drawn, stats.culled);
+            self.cull_stats = stats;
+        }
+        Ok(())
+    }
+
+    fn gui(&mut self, _window_id: WindowId, ctx: &egui::Context) -> Result<()> {
+        egui::Window::new("Objects").show(ctx, |ui| {
+            panels::game_objects(ui, &mut self.game_objects);
+        });
+        Ok(())
+    }
+
+    fn on_event(&mut self, engine: &mut Engine, event: &Event<()>) -> Result<()> {
+        let (window_id, key) = match event {
+            Event::WindowEvent {
+                window_id,
+                event:
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(key),
+                                ..
+                            },
+                        ..
+                    },
+            } => (window_id, key),
+            Event::WindowEvent {
+                event: WindowEvent::ScaleFactorChanged { scale_factor, .. },
+                ..
+            } => {
+                log::info!("scale factor: {}", scale_factor);
+                return Ok(());
+            }
+            _ => return Ok(()),
+        };
+        let typing = engine
+            .views
+            .get(window_id)
+            .and_then(|view| view.get_gui())
+            .is_some_and(|gui| gui.wants_keyboard_input());
+        if typing {
+            return Ok(());
+        }
+        match key {
+            VirtualKeyCode::V => {
+                if let Some(view) = engine.views.get_mut(window_id) {
+                    let mut config = view.render.get_swapchain_config();
+                    config.present_mode = config.present_mode.next();
+                    log::info!("present mode: {:?}", config.present_mode);
+                    view.render.set_swapchain_config(config);
+                }
+            }
+            VirtualKeyCode::B => {
+                self.show_bounds = !self.show_bounds;
+            }
+            VirtualKeyCode::N => {
+                let window_config = WindowConfig {
+                    title: format!("{} view {}", engine.config.window.title, engine.views.len()),
+                    fullscreen: FullscreenMode::Windowed,
+                    ..engine.config.window.clone()
+                };
+                let (near, far) = (engine.config.near, engine.config.far);
+                engine.open_window(window_config, MAP_FOV_Y, near, far);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn main() {
+    vulkan_hex::logger::init();
+    let config = AppConfig {
+        device: vulkan_hex::device::DeviceConfig {
+            optional_features: vulkano::device::Features {
+                sampler_anisotropy: true,
+                wide_lines: true,
+                multi_draw_indirect: true,
+                ..vulkano::device::Features::none()
+            },
+            ..vulkan_hex::device::DeviceConfig::from_env()
+        },
+        window: WindowConfig {
+            size: Some([1280, 720]),
+            min_size: Some([320, 240]),
+            ..WindowConfig::from_env()
+        },
+        render: RenderConfig {
+            swapchain: SwapchainConfig {
+                hdr: get_hdr_mode(),
+                ..RENDER_CONFIG.swapchain
+            },
+            ..RENDER_CONFIG
+        },
+        post_effects: vec![
+            PostEffect::Tonemap { exposure: 1.5 },
+            get_desaturate_effect(0.2),
+            PostEffect::Vignette {
+                intensity: 0.4,
+                radius: 0.35,
+            },
+            PostEffect::Fxaa { span: 8.0 },
+        ],
+        shadows: Some(ShadowConfig {
+            cascades: 2,
+            ..ShadowConfig::default()
+        }),
+        light: Default::default(),
+        text: get_text_config(),
+        gui: true,
+        fov_y: FOV_Y,
+        near: 0.1,
+        far: 20.0,
+    };
+    if let Err(e) = vulkan_hex::run(Hex::default(), config) {
+        log::error!("{}", e);
+        std::process::exit(1);
+    }
+}
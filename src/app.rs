@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use vulkano::instance::Instance;
+
+use winit::event::{Event, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoop, EventLoopWindowTarget};
+use winit::window::WindowId;
+
+use crate::debug_draw;
+use crate::device::{DeviceConfig, LogicalDevice};
+use crate::error::Result;
+use crate::light::DirectionalLight;
+use crate::postprocess::PostEffect;
+use crate::render::{PassContext, RenderConfig};
+use crate::shadow::ShadowConfig;
+use crate::text::{self, TextConfig};
+use crate::view::{Frame, View};
+use crate::window::WindowConfig;
+
+/// Hooks called by `run`, every error ends the event loop.
+pub trait App {
+    /// Called once, after the device and the first window are created.
+    fn init(&mut self, engine: &mut Engine) -> Result<()>;
+
+    /// Called once per frame before any view is drawn.
+    fn update(&mut self, _engine: &mut Engine) -> Result<()> {
+        Ok(())
+    }
+
+    /// Records the scene pass of one view and, with shadows on, each of its
+    /// shadow cascades (see `Frame::pass`). Called for every view with an
+    /// area to draw to.
+    fn render(&mut self, frame: &mut Frame) -> Result<()>;
+
+    /// Builds the app's part of a view's UI, called before the view is drawn
+    /// when `AppConfig::gui` is set.
+    fn gui(&mut self, _window_id: WindowId, _ctx: &egui::Context) -> Result<()> {
+        Ok(())
+    }
+
+    /// Records a pass the app added to a view's render graph.
+    fn render_pass(&mut self, _window_id: WindowId, _pass: &mut PassContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called for every event after the engine handled it.
+    fn on_event(&mut self, _engine: &mut Engine, _event: &Event<()>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Settings `run` creates the engine with.
+#[derive(Clone, Debug)]
+pub struct AppConfig {
+    pub device: DeviceConfig,
+    /// Used for the first window and as the base of `Engine::open_window`.
+    pub window: WindowConfig,
+    pub render: RenderConfig,
+    /// Fullscreen effects applied in order to every view, when empty the
+    /// scene is drawn straight to the swapchain image.
+    pub post_effects: Vec<PostEffect>,
+    /// Shadow maps for `light`, which only shades the scene when they are on.
+    pub shadows: Option<ShadowConfig>,
+    /// Initial light of every view.
+    pub light: DirectionalLight,
+    /// Font of the text queued through `text`, which is dropped when unset.
+    pub text: Option<TextConfig>,
+    /// Draw an egui UI over every view, with a window of built-in panels.
+    pub gui: bool,
+    /// Vertical field of view of the first window's camera.
+    pub fov_y: f32,
+    /// Near and far planes of the first window's camera, shadow cascades
+    /// end at `far` even with an infinite reverse-Z projection.
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            device: DeviceConfig::default(),
+            window: WindowConfig::default(),
+            render: RenderConfig::default(),
+            post_effects: vec![],
+            shadows: None,
+            light: DirectionalLight::default(),
+            text: None,
+            gui: false,
+            fov_y: std::f32::consts::FRAC_PI_2,
+            near: 0.1,
+            far: 20.0,
+        }
+    }
+}
+
+/// Device and windows shared by every hook.
+pub struct Engine {
+    pub instance: Arc<Instance>,
+    pub logical_device: LogicalDevice,
+    pub views: HashMap<WindowId, View>,
+    pub config: AppConfig,
+    /// Windows requested by `open_window`, created on the next frame.
+    pending_windows: Vec<(WindowConfig, f32, f32, f32)>,
+}
+
+impl Engine {
+    fn new(event_loop: &EventLoop<()>, config: AppConfig) -> Result<Self> {
+        let instance = crate::instance::create_instance(&config.device.validation)?;
+        let surface = crate::window::create_window(event_loop, &instance, &config.window)?;
+        let logical_device =
+            LogicalDevice::create_logical_device(&instance, &surface, &config.device)?;
+        let (fov_y, near, far) = (config.fov_y, config.near, config.far);
+        let view = View::new(&logical_device, &surface, &config, fov_y, near, far)?;
+        let mut views = HashMap::new();
+        views.insert(view.get_window_id(), view);
+        Ok(Self {
+            instance,
+            logical_device,
+            views,
+            config,
+            pending_windows: vec![],
+        })
+    }
+
+    /// Opens another view with its own camera onto whatever `App::render`
+    /// draws, the window is created before the next frame.
+    pub fn open_window(&mut self, config: WindowConfig, fov_y: f32, near: f32, far: f32) {
+        self.pending_windows.push((config, fov_y, near, far));
+    }
+
+    fn open_pending_windows(&mut self, event_loop: &EventLoopWindowTarget<()>) -> Result<()> {
+        for (window_config, fov_y, near, far) in std::mem::take(&mut self.pending_windows) {
+            let surface = crate::window::create_window(event_loop, &self.instance, &window_config)?;
+            let view = View::new(
+                &self.logical_device,
+                &surface,
+                &self.config,
+                fov_y,
+                near,
+                far,
+            )?;
+            self.views.insert(view.get_window_id(), view);
+        }
+        Ok(())
+    }
+
+    fn handle_window_event(
+        &mut self,
+        window_id: WindowId,
+        event: &WindowEvent,
+        control_flow: &mut ControlFlow,
+    ) {
+        if let Some(view) = self.views.get_mut(&window_id) {
+            view.on_window_event(event);
+        }
+        match event {
+            WindowEvent::CloseRequested => {
+                self.views.remove(&window_id);
+                if self.views.is_empty() {
+                    *control_flow = ControlFlow::Exit;
+                }
+            }
+            WindowEvent::Resized(_) => {
+                if let Some(view) = self.views.get_mut(&window_id) {
+                    view.render.recreate_swapchain();
+                }
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                // NOTE: the new inner size follows as a resize, the swapchain
+                // always uses physical pixels
+                if let Some(view) = self.views.get_mut(&window_id) {
+                    view.render.set_scale_factor(*scale_factor);
+                    view.render.recreate_swapchain();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Draws every view that has an area to draw to, returns false when
+    /// there was none. Expires debug lines and text even if nothing was
+    /// drawn.
+    fn draw(&mut self, app: &mut impl App) -> Result<bool> {
+        let mut drawn = false;
+        for view in self.views.values_mut() {
+            if view.render.check_suspended() {
+                continue;
+            }
+            drawn = true;
+            let window_id = view.get_window_id();
+            // NOTE: both callbacks need the app, they are never called at
+            // the same time
+            let app = std::cell::RefCell::new(&mut *app);
+            view.run_gui(&self.logical_device, |ctx| {
+                app.borrow_mut().gui(window_id, ctx)
+            })?;
+            view.draw(
+                &self.logical_device,
+                |frame| app.borrow_mut().render(frame),
+                |pass| app.borrow_mut().render_pass(window_id, pass),
+            )?;
+        }
+        debug_draw::end_frame();
+        text::end_frame();
+        Ok(drawn)
+    }
+
+    fn handle_event(
+        &mut self,
+        app: &mut impl App,
+        event: &Event<()>,
+        event_loop: &EventLoopWindowTarget<()>,
+        control_flow: &mut ControlFlow,
+    ) -> Result<()> {
+        if let Event::WindowEvent { window_id, event } = event {
+            self.handle_window_event(*window_id, event, control_flow);
+        }
+        app.on_event(self, event)?;
+        if let Event::RedrawEventsCleared = event {
+            self.open_pending_windows(event_loop)?;
+            app.update(self)?;
+            // NOTE: nothing to draw to, sleep until the next event
+            *control_flow = if self.draw(app)? {
+                ControlFlow::Poll
+            } else {
+                ControlFlow::Wait
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Creates the engine, calls `App::init` and runs the event loop until the
+/// last window is closed or a hook fails. Only returns on setup errors.
+pub fn run<A: App + 'static>(mut app: A, config: AppConfig) -> Result<()> {
+    let event_loop = EventLoop::new();
+    let mut engine = Engine::new(&event_loop, config)?;
+    app.init(&mut engine)?;
+    event_loop.run(move |event, event_loop, control_flow| {
+        if let Err(e) = engine.handle_event(&mut app, &event, event_loop, control_flow) {
+            log::error!("{}", e);
+            *control_flow = ControlFlow::Exit;
+        }
+    });
+}
@@ -0,0 +1,30 @@
+use glam::Mat4;
+
+/// Perspective camera parameters.
+pub struct Camera {
+    pub fov_y: f32,
+    pub near: f32,
+    pub far: f32,
+    /// Use an infinite far plane with depth reversed (near at 1, far at 0),
+    /// must match the renderer's depth setup.
+    pub reverse_z: bool,
+}
+
+impl Camera {
+    pub fn new(fov_y: f32, near: f32, far: f32, reverse_z: bool) -> Self {
+        Self {
+            fov_y,
+            near,
+            far,
+            reverse_z,
+        }
+    }
+
+    pub fn get_projection(&self, aspect: f32) -> Mat4 {
+        if self.reverse_z {
+            Mat4::perspective_infinite_reverse_lh(self.fov_y, aspect, self.near)
+        } else {
+            Mat4::perspective_lh(self.fov_y, aspect, self.near, self.far)
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use std::{
+    f32::consts::TAU,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use glam::{Mat4, Vec3};
+use vulkano::{
+    buffer::CpuBufferPool,
+    command_buffer::{AutoCommandBufferBuilder, DynamicState, PrimaryAutoCommandBuffer},
+    device::Device,
+    render_pass::RenderPass,
+};
+
+use crate::device::PoolName;
+use crate::error::Result;
+use crate::model::Aabb;
+use crate::render::OutputTransfer;
+
+mod pipeline;
+
+/// Segments used for each circle of `sphere`.
+const CIRCLE_SEGMENTS: usize = 24;
+
+/// One end of a queued line.
+#[derive(Default, Clone, Copy)]
+pub struct DebugVertex {
+    position: [f32; 3],
+    color: [f32; 4],
+}
+
+vulkano::impl_vertex!(DebugVertex, position, color);
+
+/// How queued lines are drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Style {
+    pub color: [f32; 4],
+    /// Hidden behind the scene when set, drawn on top of it otherwise.
+    pub depth_test: bool,
+    /// Number of frames the lines are drawn for, at least one.
+    pub frames: u32,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 1.0, 1.0, 1.0],
+            depth_test: true,
+            frames: 1,
+        }
+    }
+}
+
+impl Style {
+    pub fn color(color: [f32; 4]) -> Self {
+        Self {
+            color,
+            ..Self::default()
+        }
+    }
+}
+
+struct Line {
+    from: Vec3,
+    to: Vec3,
+    color: [f32; 4],
+    depth_test: bool,
+    frames_left: u32,
+}
+
+/// Shared by every thread, so lines queued off the render thread are drawn
+/// as well.
+static LINES: Mutex<Vec<Line>> = Mutex::new(Vec::new());
+
+fn get_lines() -> MutexGuard<'static, Vec<Line>> {
+    // NOTE: a panic while queueing leaves the lines intact
+    LINES.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Queues a line in world space, drawn after the scene of every view.
+pub fn line(from: Vec3, to: Vec3, style: Style) {
+    get_lines().push(Line {
+        from,
+        to,
+        color: style.color,
+        depth_test: style.depth_test,
+        frames_left: style.frames.max(1),
+    });
+}
+
+fn polyline(points: &[Vec3], style: Style) {
+    for pair in points.windows(2) {
+        line(pair[0], pair[1], style);
+    }
+}
+
+pub fn aabb(aabb: &Aabb, style: Style) {
+    let corners = aabb.corners();
+    // NOTE: corners differ in one axis when their indices differ in one bit
+    for (a, b) in [
+        (0, 1),
+        (2, 3),
+        (4, 5),
+        (6, 7),
+        (0, 2),
+        (1, 3),
+        (4, 6),
+        (5, 7),
+        (0, 4),
+        (1, 5),
+        (2, 6),
+        (3, 7),
+    ] {
+        line(corners[a], corners[b], style);
+    }
+}
+
+/// Three circles around `center`, one per axis.
+pub fn sphere(center: Vec3, radius: f32, style: Style) {
+    let circle = |point: fn(f32, f32) -> Vec3| {
+        let points: Vec<_> = (0..=CIRCLE_SEGMENTS)
+            .map(|i| {
+                let (sin, cos) = (i as f32 / CIRCLE_SEGMENTS as f32 * TAU).sin_cos();
+                center + point(cos, sin) * radius
+            })
+            .collect();
+        polyline(&points, style);
+    };
+    circle(|a, b| Vec3::new(a, b, 0.0));
+    circle(|a, b| Vec3::new(a, 0.0, b));
+    circle(|a, b| Vec3::new(0.0, a, b));
+}
+
+/// The x, y and z axes of `transform` in red, green and blue, `size` long.
+/// Only the alpha, depth test and lifetime of `style` are used.
+pub fn axes(transform: &Mat4, size: f32, style: Style) {
+    let origin = transform.transform_point3(Vec3::ZERO);
+    let alpha = style.color[3];
+    for (axis, color) in [
+        (Vec3::X, [1.0, 0.0, 0.0, alpha]),
+        (Vec3::Y, [0.0, 1.0, 0.0, alpha]),
+        (Vec3::Z, [0.0, 0.0, 1.0, alpha]),
+    ] {
+        let end = transform.transform_point3(axis * size);
+        line(origin, end, Style { color, ..style });
+    }
+}
+
+/// Square grid in the XZ plane, `cells` cells of `cell_size` on each side.
+pub fn grid(center: Vec3, cell_size: f32, cells: u32, style: Style) {
+    let half = cell_size * cells as f32 * 0.5;
+    for i in 0..=cells {
+        let offset = i as f32 * cell_size - half;
+        line(
+            center + Vec3::new(offset, 0.0, -half),
+            center + Vec3::new(offset, 0.0, half),
+            style,
+        );
+        line(
+            center + Vec3::new(-half, 0.0, offset),
+            center + Vec3::new(half, 0.0, offset),
+            style,
+        );
+    }
+}
+
+/// Flat topped hexagon in the XZ plane with corners `radius` from `center`.
+pub fn hex_outline(center: Vec3, radius: f32, style: Style) {
+    let points: Vec<_> = (0..=6)
+        .map(|i| {
+            let (sin, cos) = (i as f32 * TAU / 6.0).sin_cos();
+            center + Vec3::new(cos, 0.0, sin) * radius
+        })
+        .collect();
+    polyline(&points, style);
+}
+
+/// Edges of the volume `view_proj` maps to clip space. With an infinite far
+/// plane only the edges of the near plane are drawn.
+pub fn frustum(view_proj: &Mat4, style: Style) {
+    let inverse = view_proj.inverse();
+    let corners: Vec<_> = [0.0, 1.0]
+        .iter()
+        .flat_map(|&z| {
+            [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+                .iter()
+                .map(move |&(x, y)| inverse * glam::Vec4::new(x, y, z, 1.0))
+                .collect::<Vec<_>>()
+        })
+        .map(|corner| {
+            if corner.w.abs() > f32::EPSILON {
+                Some(corner.truncate() / corner.w)
+            } else {
+                None
+            }
+        })
+        .collect();
+    for i in 0..4 {
+        let j = (i + 1) % 4;
+        for (a, b) in [(i, j), (i + 4, j + 4), (i, i + 4)] {
+            if let (Some(a), Some(b)) = (corners[a], corners[b]) {
+                line(a, b, style);
+            }
+        }
+    }
+}
+
+/// Counts down the lifetime of every queued line and drops the expired ones,
+/// called once all views were drawn.
+pub fn end_frame() {
+    get_lines().retain_mut(|line| {
+        line.frames_left -= 1;
+        line.frames_left > 0
+    });
+}
+
+/// Draws the queued lines inside the scene pass.
+pub struct DebugRenderer {
+    depth_tested: Arc<pipeline::LinePipeline>,
+    overlay: Arc<pipeline::LinePipeline>,
+    vertex_pool: CpuBufferPool<DebugVertex>,
+    vertex_name: PoolName,
+    output_transfer: OutputTransfer,
+}
+
+impl DebugRenderer {
+    pub fn new(
+        device: &Arc<Device>,
+        renderpass: &Arc<RenderPass>,
+        reverse_z: bool,
+        output_transfer: OutputTransfer,
+    ) -> Result<Self> {
+        Ok(Self {
+            depth_tested: pipeline::get_pipeline(device, renderpass, reverse_z, true)?,
+            overlay: pipeline::get_pipeline(device, renderpass, reverse_z, false)?,
+            vertex_pool: CpuBufferPool::vertex_buffer(device.clone()),
+            vertex_name: PoolName::new("debug line vertices"),
+            output_transfer,
+        })
+    }
+
+    pub fn render(
+        &self,
+        cmd_builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        dynamicstate: &DynamicState,
+        view_proj: &Mat4,
+    ) -> Result<()> {
+        let mut depth_tested = vec![];
+        let mut overlay = vec![];
+        for line in get_lines().iter() {
+            let vertices = if line.depth_test {
+                &mut depth_tested
+            } else {
+                &mut overlay
+            };
+            for position in [line.from, line.to] {
+                vertices.push(DebugVertex {
+                    position: position.into(),
+                    color: line.color,
+                });
+            }
+        }
+        let push_data = crate::shaders::debug_vs::ty::PushConstantData {
+            view_proj: view_proj.to_cols_array_2d(),
+            output_transfer: self.output_transfer as u32,
+        };
+        for (pipeline, vertices) in [(&self.depth_tested, depth_tested), (&self.overlay, overlay)] {
+            if vertices.is_empty() {
+                continue;
+            }
+            let vertex_buffer = self.vertex_pool.chunk(vertices)?;
+            self.vertex_name.apply(&vertex_buffer);
+            cmd_builder.draw(
+                pipeline.clone(),
+                dynamicstate,
+                vertex_buffer,
+                (),
+                push_data,
+                vec![],
+            )?;
+        }
+        Ok(())
+    }
+}
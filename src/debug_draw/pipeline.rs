@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use vulkano::descriptor::PipelineLayoutAbstract;
+use vulkano::device::Device;
+use vulkano::pipeline::blend::AttachmentBlend;
+use vulkano::pipeline::depth_stencil::{Compare, DepthStencil};
+use vulkano::pipeline::{vertex::SingleBufferDefinition, GraphicsPipeline};
+use vulkano::render_pass::RenderPass;
+
+use super::DebugVertex;
+use crate::device::set_debug_name;
+use crate::error::Result;
+use crate::render::graph::get_subpass;
+use crate::shaders::{debug_vs, fs};
+
+pub type LinePipeline = GraphicsPipeline<
+    SingleBufferDefinition<DebugVertex>,
+    Box<dyn PipelineLayoutAbstract + Send + Sync + 'static>,
+>;
+
+/// Line list pipeline, tested against the scene's depth without writing it
+/// when `depth_test` is set.
+pub fn get_pipeline(
+    device: &Arc<Device>,
+    renderpass: &Arc<RenderPass>,
+    reverse_z: bool,
+    depth_test: bool,
+) -> Result<Arc<LinePipeline>> {
+    let depth_stencil = if depth_test {
+        DepthStencil {
+            depth_compare: if reverse_z {
+                Compare::GreaterOrEqual
+            } else {
+                Compare::LessOrEqual
+            },
+            depth_write: false,
+            ..DepthStencil::simple_depth_test()
+        }
+    } else {
+        DepthStencil::disabled()
+    };
+    let vs = debug_vs::Shader::load(device.clone())?;
+    let fs = fs::Shader::load(device.clone())?;
+
+    let pipeline = Arc::new(
+        GraphicsPipeline::start()
+            .vertex_input_single_buffer::<DebugVertex>()
+            .vertex_shader(vs.main_entry_point(), ())
+            .line_list()
+            .viewports_dynamic_scissors_irrelevant(1)
+            .fragment_shader(fs.main_entry_point(), ())
+            .depth_stencil(depth_stencil)
+            .blend_collective(AttachmentBlend::alpha_blending())
+            .render_pass(get_subpass(renderpass)?)
+            .build(device.clone())?,
+    );
+    let name = if depth_test {
+        "debug line pipeline"
+    } else {
+        "debug overlay line pipeline"
+    };
+    set_debug_name(device, &*pipeline, name);
+    Ok(pipeline)
+}
@@ -1,89 +1,130 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use vulkano::device::{Device, DeviceExtensions, Features, Queue};
-use vulkano::instance::debug::{DebugCallback, MessageSeverity, MessageType};
-use vulkano::instance::{Instance, PhysicalDevice};
+use vulkano::instance::debug::DebugCallback;
+use vulkano::instance::{Instance, QueueFamily};
 use vulkano::swapchain::Surface;
 
 use winit::window::Window;
 
+use crate::error::{Error, Result};
+
+mod naming;
+mod selection;
+mod validation;
+
+pub use naming::{debug_utils_enabled, set_debug_name, PoolName};
+pub use selection::DeviceConfig;
+pub use validation::ValidationConfig;
+
+/// Queue priorities, rendering wins when the device has to choose.
+const GRAPHICS_PRIORITY: f32 = 1.0;
+const COMPUTE_PRIORITY: f32 = 0.5;
+const TRANSFER_PRIORITY: f32 = 0.5;
+
 pub struct LogicalDevice {
     pub device: Arc<Device>,
     pub graphical_queue: Arc<Queue>,
     pub present_queue: Arc<Queue>,
+    /// Dedicated async compute queue, or the graphical queue when the device
+    /// has none.
+    pub compute_queue: Arc<Queue>,
+    /// Dedicated transfer queue, or the graphical queue when the device has
+    /// none.
+    pub transfer_queue: Arc<Queue>,
+    validation_errors: Arc<AtomicUsize>,
+    panic_on_validation_error: bool,
     _debug_callback: Option<DebugCallback>,
 }
 
 impl LogicalDevice {
-    pub fn create_logical_device(instance: &Arc<Instance>, surface: &Arc<Surface<Window>>) -> Self {
-        let _debug_callback = Self::setup_debug_callback(&instance);
-        let physical = Self::create_phy_device(&instance);
-        let mut graphical_queue_family = None;
-        let mut present_queue_family = None;
-        for queue_family in physical.queue_families() {
-            if graphical_queue_family.is_none() && queue_family.supports_graphics() {
-                graphical_queue_family = Some(queue_family.clone());
-            }
-            if present_queue_family.is_none() && surface.is_supported(queue_family).unwrap_or(false)
+    pub fn create_logical_device(
+        instance: &Arc<Instance>,
+        surface: &Arc<Surface<Window>>,
+        config: &DeviceConfig,
+    ) -> Result<Self> {
+        let validation_errors = Arc::new(AtomicUsize::new(0));
+        let _debug_callback =
+            validation::setup_debug_callback(instance, &config.validation, &validation_errors);
+        let physical = selection::select_physical_device(instance, surface, config)?;
+        let (graphical_queue_family, present_queue_family) =
+            selection::find_queue_families(physical, surface)?;
+        let compute_queue_family = selection::find_compute_family(physical);
+        let transfer_queue_family = selection::find_transfer_family(physical);
+        let mut queue_families = vec![(graphical_queue_family, GRAPHICS_PRIORITY)];
+        let others = vec![
+            Some((present_queue_family, GRAPHICS_PRIORITY)),
+            compute_queue_family.map(|family| (family, COMPUTE_PRIORITY)),
+            transfer_queue_family.map(|family| (family, TRANSFER_PRIORITY)),
+        ];
+        for (family, priority) in others.into_iter().flatten() {
+            if queue_families
+                .iter()
+                .all(|(other, _)| other.id() != family.id())
             {
-                present_queue_family = Some(queue_family.clone());
+                queue_families.push((family, priority));
             }
         }
-        let graphical_queue_family =
-            graphical_queue_family.expect("could not find graphical queue family");
-        let present_queue_family =
-            present_queue_family.expect("could not find present queue family");
-        let mut queue_families = vec![graphical_queue_family];
-        if present_queue_family != graphical_queue_family {
-            queue_families.push(present_queue_family);
-        }
 
-        let (device, mut queues) = {
-            Device::new(
-                physical,
-                &Features {
-                    // NOTE: this is to allow non filled triangle (swapchain)
-                    fill_mode_non_solid: true,
-                    ..Features::none()
-                },
-                &DeviceExtensions {
-                    // NOTE: this is to allow swapchain
-                    khr_swapchain: true,
-                    ..DeviceExtensions::none()
-                },
-                // TODO: figure out the priority
-                queue_families.into_iter().map(|p| (p, 1.0)),
-            )
-            .expect("failed to create device")
-        };
+        let (device, queues) = Device::new(
+            physical,
+            &config.get_features(physical),
+            &config.get_extensions(physical),
+            queue_families,
+        )?;
 
-        let graphical_queue = queues.next().expect("did not get any queue");
-        let present_queue = queues.next().unwrap_or(graphical_queue.clone());
-        Self {
+        // one queue was created per family, in the order requested
+        let queues: Vec<_> = queues.collect();
+        let queue_for = |family: QueueFamily| {
+            queues
+                .iter()
+                .find(|queue| queue.family().id() == family.id())
+                .cloned()
+        };
+        let graphical_queue =
+            queue_for(graphical_queue_family).ok_or(Error::MissingQueueFamily("graphical"))?;
+        let present_queue =
+            queue_for(present_queue_family).unwrap_or_else(|| graphical_queue.clone());
+        let compute_queue = compute_queue_family
+            .and_then(queue_for)
+            .unwrap_or_else(|| graphical_queue.clone());
+        let transfer_queue = transfer_queue_family
+            .and_then(queue_for)
+            .unwrap_or_else(|| graphical_queue.clone());
+        Ok(Self {
             device,
             graphical_queue,
             present_queue,
+            compute_queue,
+            transfer_queue,
+            validation_errors,
+            panic_on_validation_error: config.validation.panic_on_error,
             _debug_callback,
-        }
+        })
+    }
+
+    /// Features enabled on the device, required plus supported optional ones.
+    pub fn enabled_features(&self) -> &Features {
+        self.device.enabled_features()
     }
 
-    fn create_phy_device<'a>(instance: &'a Arc<Instance>) -> PhysicalDevice<'a> {
-        let physical = PhysicalDevice::enumerate(instance)
-            .next()
-            .expect("no device available");
-        println!("{}|{:?}|", physical.name(), physical.ty());
-        physical
+    /// Extensions enabled on the device, required plus supported optional ones.
+    pub fn enabled_extensions(&self) -> &DeviceExtensions {
+        self.device.loaded_extensions()
     }
 
-    fn setup_debug_callback(instance: &Arc<Instance>) -> Option<DebugCallback> {
-        if !crate::instance::ENABLE_VALIDATION_LAYERS {
-            return None;
+    /// Number of errors reported by the validation layers so far.
+    pub fn validation_error_count(&self) -> usize {
+        self.validation_errors.load(Ordering::Relaxed)
+    }
+
+    /// Panics if the validation layers reported an error and
+    /// `ValidationConfig::panic_on_error` is set.
+    pub fn check_validation(&self) {
+        let errors = self.validation_error_count();
+        if self.panic_on_validation_error && errors > 0 {
+            panic!("validation layers reported {} error(s)", errors);
         }
-        let mut serverity = MessageSeverity::errors_and_warnings();
-        serverity.verbose = false;
-        DebugCallback::new(instance, serverity, MessageType::all(), |msg| {
-            println!("validation layer: {:?}", msg.description);
-        })
-        .ok()
     }
 }
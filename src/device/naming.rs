@@ -0,0 +1,59 @@
+use std::ffi::CString;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use vulkano::buffer::BufferAccess;
+use vulkano::device::Device;
+use vulkano::{VulkanHandle, VulkanObject};
+
+/// Whether `VK_EXT_debug_utils` is loaded, object names and command buffer
+/// labels must not be used without it.
+pub fn debug_utils_enabled(device: &Device) -> bool {
+    device.instance().loaded_extensions().ext_debug_utils
+}
+
+/// Names `object` in validation messages and capture tools, does nothing
+/// without `VK_EXT_debug_utils`.
+///
+/// `object` must have been created from `device`.
+pub fn set_debug_name<T: VulkanObject>(device: &Device, object: &T, name: &str) {
+    if !debug_utils_enabled(device) {
+        return;
+    }
+    let name = match CString::new(name) {
+        Ok(name) => name,
+        Err(_) => return,
+    };
+    // SAFETY: the handle and its type come from the same object, which the
+    // caller created from `device`.
+    let result =
+        unsafe { device.set_object_name_raw(T::TYPE, object.internal_object().value(), &name) };
+    if let Err(e) = result {
+        log::warn!("failed to name {:?}: {}", name, e);
+    }
+}
+
+/// Name of the buffers behind a `CpuBufferPool`'s chunks. A buffer is only
+/// named when a chunk comes from a different one than the last, so names are
+/// not set again every frame.
+pub struct PoolName {
+    name: &'static str,
+    last: AtomicU64,
+}
+
+impl PoolName {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            last: AtomicU64::new(0),
+        }
+    }
+
+    /// Names the buffer `chunk` lives in if it was not named yet.
+    pub fn apply(&self, chunk: &impl BufferAccess) {
+        let buffer = chunk.inner().buffer;
+        let handle = buffer.internal_object().value();
+        if self.last.swap(handle, Ordering::Relaxed) != handle {
+            set_debug_name(chunk.device(), buffer, self.name);
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use std::sync::Arc;
+
+use vulkano::device::{DeviceExtensions, Features};
+use vulkano::instance::{Instance, PhysicalDevice, PhysicalDeviceType, QueueFamily};
+use vulkano::swapchain::Surface;
+
+use winit::window::Window;
+
+use super::ValidationConfig;
+use crate::error::{Error, Result};
+
+/// Restricts which GPU is used, see `DeviceConfig::from_env`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GpuOverride {
+    /// Position in `PhysicalDevice::enumerate`.
+    Index(usize),
+    /// Case insensitive substring of the device name.
+    Name(String),
+    /// PCI vendor id.
+    Vendor(u32),
+}
+
+impl GpuOverride {
+    /// Parses a number as an index, `vendor:<name or hex id>` as a vendor and
+    /// anything else as a name substring.
+    pub fn parse(value: &str) -> Self {
+        if let Ok(index) = value.parse() {
+            return Self::Index(index);
+        }
+        if let Some(vendor) = value.strip_prefix("vendor:") {
+            let id = match vendor.to_lowercase().as_str() {
+                "nvidia" => Some(0x10de),
+                "amd" => Some(0x1002),
+                "intel" => Some(0x8086),
+                "arm" => Some(0x13b5),
+                "qualcomm" => Some(0x5143),
+                "apple" => Some(0x106b),
+                id => u32::from_str_radix(id.trim_start_matches("0x"), 16).ok(),
+            };
+            if let Some(id) = id {
+                return Self::Vendor(id);
+            }
+        }
+        Self::Name(value.to_lowercase())
+    }
+
+    fn matches(&self, physical: &PhysicalDevice) -> bool {
+        match self {
+            Self::Index(index) => physical.index() == *index,
+            Self::Name(name) => physical.name().to_lowercase().contains(name.as_str()),
+            Self::Vendor(id) => physical.pci_vendor_id() == *id,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DeviceConfig {
+    /// Use the first suitable device matching this instead of the best scored.
+    pub gpu: Option<GpuOverride>,
+    /// Log every device with its score or the reasons it was rejected.
+    pub list_gpus: bool,
+    /// Devices lacking any of these are rejected.
+    pub required_features: Features,
+    /// Enabled when the selected device supports them.
+    pub optional_features: Features,
+    /// Devices lacking any of these are rejected.
+    pub required_extensions: DeviceExtensions,
+    /// Enabled when the selected device supports them.
+    pub optional_extensions: DeviceExtensions,
+    pub validation: ValidationConfig,
+}
+
+impl Default for DeviceConfig {
+    fn default() -> Self {
+        Self {
+            gpu: None,
+            list_gpus: false,
+            required_features: Features {
+                // NOTE: this is to allow non filled triangle (swapchain)
+                fill_mode_non_solid: true,
+                ..Features::none()
+            },
+            optional_features: Features::none(),
+            required_extensions: DeviceExtensions {
+                // NOTE: this is to allow swapchain
+                khr_swapchain: true,
+                ..DeviceExtensions::none()
+            },
+            optional_extensions: DeviceExtensions::none(),
+            validation: ValidationConfig::default(),
+        }
+    }
+}
+
+impl DeviceConfig {
+    /// Reads `HEX_GPU` (see `GpuOverride::parse`), `HEX_LIST_GPUS` and the
+    /// validation settings.
+    pub fn from_env() -> Self {
+        Self {
+            gpu: std::env::var("HEX_GPU")
+                .ok()
+                .map(|value| GpuOverride::parse(&value)),
+            list_gpus: std::env::var_os("HEX_LIST_GPUS").is_some(),
+            validation: ValidationConfig::from_env(),
+            ..Self::default()
+        }
+    }
+
+    /// Required features plus the optional ones `physical` supports.
+    pub(super) fn get_features(&self, physical: PhysicalDevice) -> Features {
+        // `Features` has no union, keep what is supported and either
+        // required or optional instead.
+        let supported = physical.supported_features();
+        let unwanted = supported
+            .difference(&self.required_features)
+            .difference(&self.optional_features);
+        supported.difference(&unwanted)
+    }
+
+    /// Required extensions plus the optional ones `physical` supports.
+    pub(super) fn get_extensions(&self, physical: PhysicalDevice) -> DeviceExtensions {
+        let supported = DeviceExtensions::supported_by_device(physical);
+        self.required_extensions
+            .union(&self.optional_extensions.intersection(&supported))
+    }
+}
+
+/// First graphics family and first family able to present to `surface`,
+/// preferring one family that does both.
+pub(super) fn find_queue_families<'a>(
+    physical: PhysicalDevice<'a>,
+    surface: &Arc<Surface<Window>>,
+) -> Result<(QueueFamily<'a>, QueueFamily<'a>)> {
+    let presents = |family: &QueueFamily| surface.is_supported(*family).unwrap_or(false);
+    if let Some(family) = physical
+        .queue_families()
+        .find(|family| family.supports_graphics() && presents(family))
+    {
+        return Ok((family, family));
+    }
+    let graphical = physical
+        .queue_families()
+        .find(QueueFamily::supports_graphics)
+        .ok_or(Error::MissingQueueFamily("graphical"))?;
+    let present = physical
+        .queue_families()
+        .find(presents)
+        .ok_or(Error::MissingQueueFamily("present"))?;
+    Ok((graphical, present))
+}
+
+/// Family with compute but no graphics support, work submitted to it can
+/// run alongside rendering.
+pub(super) fn find_compute_family(physical: PhysicalDevice) -> Option<QueueFamily> {
+    physical
+        .queue_families()
+        .find(|family| family.supports_compute() && !family.supports_graphics())
+}
+
+/// Family supporting only transfers, usually backed by a dedicated copy
+/// engine.
+pub(super) fn find_transfer_family(physical: PhysicalDevice) -> Option<QueueFamily> {
+    physical.queue_families().find(|family| {
+        family.explicitly_supports_transfers()
+            && !family.supports_graphics()
+            && !family.supports_compute()
+    })
+}
+
+/// Higher is better, or the reasons the device cannot be used.
+fn score(
+    physical: PhysicalDevice,
+    surface: &Arc<Surface<Window>>,
+    config: &DeviceConfig,
+) -> std::result::Result<u64, Vec<String>> {
+    let mut rejected = vec![];
+    let missing_features = config
+        .required_features
+        .difference(physical.supported_features());
+    if missing_features != Features::none() {
+        rejected.push(format!("missing features {:?}", missing_features));
+    }
+    let missing_extensions = config
+        .required_extensions
+        .difference(&DeviceExtensions::supported_by_device(physical));
+    if missing_extensions != DeviceExtensions::none() {
+        rejected.push(format!("missing extensions {:?}", missing_extensions));
+    }
+    let families = find_queue_families(physical, surface);
+    if let Err(e) = &families {
+        rejected.push(e.to_string());
+    }
+    if !rejected.is_empty() {
+        return Err(rejected);
+    }
+
+    let mut score = match physical.ty() {
+        PhysicalDeviceType::DiscreteGpu => 1000,
+        PhysicalDeviceType::IntegratedGpu => 500,
+        PhysicalDeviceType::VirtualGpu => 200,
+        PhysicalDeviceType::Cpu => 100,
+        PhysicalDeviceType::Other => 0,
+    };
+    if let Ok((graphical, present)) = families {
+        if graphical == present {
+            score += 50;
+        }
+    }
+    let local_memory: usize = physical
+        .memory_heaps()
+        .filter(|heap| heap.is_device_local())
+        .map(|heap| heap.size())
+        .sum();
+    Ok(score + (local_memory >> 26) as u64)
+}
+
+/// Picks the suitable device with the highest score, or the first suitable
+/// one matching `config.gpu`.
+pub(super) fn select_physical_device<'a>(
+    instance: &'a Arc<Instance>,
+    surface: &Arc<Surface<Window>>,
+    config: &DeviceConfig,
+) -> Result<PhysicalDevice<'a>> {
+    let candidates: Vec<_> = PhysicalDevice::enumerate(instance)
+        .map(|physical| (physical, score(physical, surface, config)))
+        .collect();
+    let suitable = || {
+        candidates
+            .iter()
+            .filter_map(|(physical, score)| score.as_ref().ok().map(|score| (*physical, *score)))
+    };
+    let best = || suitable().max_by_key(|(_, score)| *score);
+    let chosen = match &config.gpu {
+        Some(gpu) => suitable()
+            .find(|(physical, _)| gpu.matches(physical))
+            .or_else(|| {
+                log::warn!("no suitable device matches {:?}, using best scored", gpu);
+                best()
+            }),
+        None => best(),
+    };
+
+    // NOTE: listed before failing so rejected devices show why
+    if config.list_gpus {
+        let chosen_index = chosen.map(|(physical, _)| physical.index());
+        for (candidate, score) in &candidates {
+            let marker = if Some(candidate.index()) == chosen_index {
+                "*"
+            } else {
+                " "
+            };
+            let verdict = match score {
+                Ok(score) => format!("score {}", score),
+                Err(reasons) => format!("rejected: {}", reasons.join(", ")),
+            };
+            log::info!(
+                "{}{}|{}|{:?}|{:#06x}|{}",
+                marker,
+                candidate.index(),
+                candidate.name(),
+                candidate.ty(),
+                candidate.pci_vendor_id(),
+                verdict
+            );
+        }
+    }
+    let (physical, _) = chosen.ok_or(Error::NoPhysicalDevice)?;
+    log::info!("using {} ({:?})", physical.name(), physical.ty());
+    Ok(physical)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn number_is_an_index() {
+        assert_eq!(GpuOverride::parse("1"), GpuOverride::Index(1));
+    }
+
+    #[test]
+    fn vendor_by_name_or_hex_id() {
+        assert_eq!(
+            GpuOverride::parse("vendor:NVIDIA"),
+            GpuOverride::Vendor(0x10de)
+        );
+        assert_eq!(
+            GpuOverride::parse("vendor:0x1002"),
+            GpuOverride::Vendor(0x1002)
+        );
+        assert_eq!(
+            GpuOverride::parse("vendor:8086"),
+            GpuOverride::Vendor(0x8086)
+        );
+    }
+
+    #[test]
+    fn anything_else_is_a_lowercase_name() {
+        assert_eq!(
+            GpuOverride::parse("GeForce RTX"),
+            GpuOverride::Name("geforce rtx".into())
+        );
+        assert_eq!(
+            GpuOverride::parse("vendor:unknown"),
+            GpuOverride::Name("vendor:unknown".into())
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use log::{Level, LevelFilter};
+use vulkano::instance::debug::{DebugCallback, Message, MessageSeverity, MessageType};
+use vulkano::instance::Instance;
+
+/// Log target of validation layer messages.
+const TARGET: &str = "vulkan::validation";
+
+#[derive(Clone, Copy, Debug)]
+pub struct ValidationConfig {
+    /// Load the validation layers, startup continues without them when they
+    /// are not installed.
+    pub enabled: bool,
+    /// Least severe message requested from the layers, `Debug` and below
+    /// include verbose messages. Messages are logged under the
+    /// `vulkan::validation` target, the app's logger must let that target
+    /// through at this level or they are dropped.
+    pub level: LevelFilter,
+    pub types: MessageType,
+    /// Panic from `LogicalDevice::check_validation` once an error has been
+    /// reported, errors are always counted.
+    pub panic_on_error: bool,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: cfg!(debug_assertions),
+            level: LevelFilter::Warn,
+            types: MessageType::all(),
+            panic_on_error: false,
+        }
+    }
+}
+
+impl ValidationConfig {
+    /// Reads `HEX_VALIDATION` (`1`/`on` or `0`/`off`), the `--validation`
+    /// and `--no-validation` flags, `HEX_VALIDATION_LEVEL` (a log level) and
+    /// `HEX_VALIDATION_PANIC`. The flags win over the environment, which wins
+    /// over the build profile default.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let from_var = match std::env::var("HEX_VALIDATION").as_deref() {
+            Ok("1") | Ok("on") => Some(true),
+            Ok("0") | Ok("off") => Some(false),
+            _ => None,
+        };
+        let from_args = std::env::args()
+            .skip(1)
+            .fold(None, |enabled, arg| match arg.as_str() {
+                "--validation" => Some(true),
+                "--no-validation" => Some(false),
+                _ => enabled,
+            });
+        Self {
+            enabled: from_args.or(from_var).unwrap_or(default.enabled),
+            level: std::env::var("HEX_VALIDATION_LEVEL")
+                .ok()
+                .and_then(|value| crate::logger::parse_level(&value))
+                .unwrap_or(default.level),
+            panic_on_error: std::env::var_os("HEX_VALIDATION_PANIC").is_some(),
+            ..default
+        }
+    }
+
+    fn get_severity(&self) -> MessageSeverity {
+        MessageSeverity {
+            error: self.level >= Level::Error,
+            warning: self.level >= Level::Warn,
+            information: self.level >= Level::Info,
+            verbose: self.level >= Level::Debug,
+        }
+    }
+}
+
+fn get_level(severity: &MessageSeverity) -> Level {
+    if severity.error {
+        Level::Error
+    } else if severity.warning {
+        Level::Warn
+    } else if severity.information {
+        Level::Info
+    } else {
+        Level::Debug
+    }
+}
+
+fn get_type_name(ty: &MessageType) -> &'static str {
+    if ty.validation {
+        "validation"
+    } else if ty.performance {
+        "performance"
+    } else {
+        "general"
+    }
+}
+
+/// Forwards layer messages to `log` and counts errors in `errors`.
+///
+/// Panics inside the callback are swallowed by vulkano, so reacting to
+/// errors is left to whoever reads the count.
+///
+/// vulkano's `Message` does not expose the objects a message is about, so
+/// names set with `set_debug_name` only show up where the layers quote them
+/// in the description.
+pub(super) fn setup_debug_callback(
+    instance: &Arc<Instance>,
+    config: &ValidationConfig,
+    errors: &Arc<AtomicUsize>,
+) -> Option<DebugCallback> {
+    // only enabled by `create_instance` when the layers are loaded
+    if !instance.loaded_extensions().ext_debug_utils {
+        return None;
+    }
+    let errors = errors.clone();
+    let callback = move |msg: &Message| {
+        if msg.severity.error {
+            errors.fetch_add(1, Ordering::Relaxed);
+        }
+        log::log!(
+            target: TARGET,
+            get_level(&msg.severity),
+            "[{}] [{}] {}",
+            get_type_name(&msg.ty),
+            msg.layer_prefix.unwrap_or("unknown"),
+            msg.description
+        );
+    };
+    match DebugCallback::new(instance, config.get_severity(), config.types, callback) {
+        Ok(callback) => Some(callback),
+        Err(e) => {
+            log::warn!("failed to register validation callback: {}", e);
+            None
+        }
+    }
+}
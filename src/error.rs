@@ -0,0 +1,205 @@
+use std::fmt;
+
+use vulkano::command_buffer::{
+    AutoCommandBufferBuilderContextError, BeginRenderPassError, BuildError, CommandBufferExecError,
+    DebugMarkerError, DrawError, DrawIndexedError,
+};
+use vulkano::descriptor::descriptor_set::{
+    PersistentDescriptorSetBuildError, PersistentDescriptorSetError,
+};
+use vulkano::device::DeviceCreationError;
+use vulkano::image::{view::ImageViewCreationError, ImageCreationError};
+use vulkano::instance::{InstanceCreationError, LayersListError};
+use vulkano::memory::DeviceMemoryAllocError;
+use vulkano::pipeline::GraphicsPipelineCreationError;
+use vulkano::render_pass::{FramebufferCreationError, RenderPassCreationError};
+use vulkano::sampler::SamplerCreationError;
+use vulkano::swapchain::{AcquireError, CapabilitiesError, SwapchainCreationError};
+use vulkano::sync::FlushError;
+use vulkano::OomError;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Everything that can go wrong while setting up or driving the renderer.
+#[derive(Debug)]
+pub enum Error {
+    Instance(InstanceCreationError),
+    LayersList(LayersListError),
+    Window(vulkano_win::CreationError),
+    NoPhysicalDevice,
+    MissingQueueFamily(&'static str),
+    Device(DeviceCreationError),
+    Surface(CapabilitiesError),
+    Unsupported(&'static str),
+    Swapchain(SwapchainCreationError),
+    Acquire(AcquireError),
+    /// A frame was submitted without an acquired swapchain image.
+    NotAcquired,
+    RenderPass(RenderPassCreationError),
+    /// Passes of a render graph that cannot be scheduled.
+    RenderGraph(String),
+    /// A change `PostChain::set_effect` cannot apply.
+    PostEffect(&'static str),
+    Framebuffer(FramebufferCreationError),
+    Pipeline(GraphicsPipelineCreationError),
+    Allocation(DeviceMemoryAllocError),
+    Image(ImageCreationError),
+    ImageView(ImageViewCreationError),
+    Sampler(SamplerCreationError),
+    DescriptorSet(PersistentDescriptorSetError),
+    BuildDescriptorSet(PersistentDescriptorSetBuildError),
+    Io(std::io::Error),
+    Font(ab_glyph::InvalidFont),
+    Oom(OomError),
+    BeginRenderPass(BeginRenderPassError),
+    Draw(DrawError),
+    DrawIndexed(DrawIndexedError),
+    DebugMarker(DebugMarkerError),
+    EndRenderPass(AutoCommandBufferBuilderContextError),
+    BuildCommandBuffer(BuildError),
+    Execute(CommandBufferExecError),
+    Flush(FlushError),
+    /// The device stopped responding, everything created from it must be
+    /// dropped and recreated.
+    DeviceLost,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Instance(e) => write!(f, "failed to create Vulkan instance: {}", e),
+            Self::LayersList(e) => write!(f, "failed to list instance layers: {}", e),
+            Self::Window(e) => write!(f, "failed to create window: {}", e),
+            Self::NoPhysicalDevice => write!(f, "no device available"),
+            Self::MissingQueueFamily(kind) => write!(f, "could not find {} queue family", kind),
+            Self::Device(e) => write!(f, "failed to create device: {}", e),
+            Self::Surface(e) => write!(f, "failed to query surface: {}", e),
+            Self::Unsupported(kind) => write!(f, "no supported {}", kind),
+            Self::Swapchain(e) => write!(f, "failed to create swapchain: {}", e),
+            Self::Acquire(e) => write!(f, "failed to acquire swapchain image: {}", e),
+            Self::NotAcquired => write!(f, "no swapchain image acquired for the frame"),
+            Self::RenderPass(e) => write!(f, "failed to create render pass: {}", e),
+            Self::RenderGraph(e) => write!(f, "invalid render graph: {}", e),
+            Self::PostEffect(e) => write!(f, "invalid post effect change: {}", e),
+            Self::Framebuffer(e) => write!(f, "failed to create framebuffer: {}", e),
+            Self::Pipeline(e) => write!(f, "failed to create pipeline: {}", e),
+            Self::Allocation(e) => write!(f, "failed to allocate memory: {}", e),
+            Self::Image(e) => write!(f, "failed to create image: {}", e),
+            Self::ImageView(e) => write!(f, "failed to create image view: {}", e),
+            Self::Sampler(e) => write!(f, "failed to create sampler: {}", e),
+            Self::DescriptorSet(e) => write!(f, "failed to add descriptor: {}", e),
+            Self::BuildDescriptorSet(e) => write!(f, "failed to build descriptor set: {}", e),
+            Self::Io(e) => write!(f, "failed to read file: {}", e),
+            Self::Font(e) => write!(f, "failed to load font: {}", e),
+            Self::Oom(e) => write!(f, "out of memory: {}", e),
+            Self::BeginRenderPass(e) => write!(f, "failed to begin render pass: {}", e),
+            Self::Draw(e) => write!(f, "failed to record draw: {}", e),
+            Self::DrawIndexed(e) => write!(f, "failed to record indexed draw: {}", e),
+            Self::DebugMarker(e) => write!(f, "failed to record debug label: {}", e),
+            Self::EndRenderPass(e) => write!(f, "failed to end render pass: {}", e),
+            Self::BuildCommandBuffer(e) => write!(f, "failed to build command buffer: {}", e),
+            Self::Execute(e) => write!(f, "failed to execute command buffer: {}", e),
+            Self::Flush(e) => write!(f, "failed to flush future: {}", e),
+            Self::DeviceLost => write!(f, "device lost"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Instance(e) => Some(e),
+            Self::LayersList(e) => Some(e),
+            Self::Window(e) => Some(e),
+            Self::Device(e) => Some(e),
+            Self::Surface(e) => Some(e),
+            Self::Swapchain(e) => Some(e),
+            Self::Acquire(e) => Some(e),
+            Self::RenderPass(e) => Some(e),
+            Self::Framebuffer(e) => Some(e),
+            Self::Pipeline(e) => Some(e),
+            Self::Allocation(e) => Some(e),
+            Self::Image(e) => Some(e),
+            Self::ImageView(e) => Some(e),
+            Self::Sampler(e) => Some(e),
+            Self::DescriptorSet(e) => Some(e),
+            Self::BuildDescriptorSet(e) => Some(e),
+            Self::Io(e) => Some(e),
+            Self::Font(e) => Some(e),
+            Self::Oom(e) => Some(e),
+            Self::BeginRenderPass(e) => Some(e),
+            Self::Draw(e) => Some(e),
+            Self::DrawIndexed(e) => Some(e),
+            Self::DebugMarker(e) => Some(e),
+            Self::EndRenderPass(e) => Some(e),
+            Self::BuildCommandBuffer(e) => Some(e),
+            Self::Execute(e) => Some(e),
+            Self::Flush(e) => Some(e),
+            Self::NoPhysicalDevice
+            | Self::MissingQueueFamily(_)
+            | Self::Unsupported(_)
+            | Self::NotAcquired
+            | Self::RenderGraph(_)
+            | Self::PostEffect(_)
+            | Self::DeviceLost => None,
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($($variant:ident($error:ty)),* $(,)?) => {
+        $(
+            impl From<$error> for Error {
+                fn from(e: $error) -> Self {
+                    Self::$variant(e)
+                }
+            }
+        )*
+    };
+}
+
+impl_from!(
+    Instance(InstanceCreationError),
+    LayersList(LayersListError),
+    Window(vulkano_win::CreationError),
+    Device(DeviceCreationError),
+    Surface(CapabilitiesError),
+    Swapchain(SwapchainCreationError),
+    RenderPass(RenderPassCreationError),
+    Framebuffer(FramebufferCreationError),
+    Pipeline(GraphicsPipelineCreationError),
+    Allocation(DeviceMemoryAllocError),
+    Image(ImageCreationError),
+    ImageView(ImageViewCreationError),
+    Sampler(SamplerCreationError),
+    DescriptorSet(PersistentDescriptorSetError),
+    BuildDescriptorSet(PersistentDescriptorSetBuildError),
+    Io(std::io::Error),
+    Font(ab_glyph::InvalidFont),
+    Oom(OomError),
+    BeginRenderPass(BeginRenderPassError),
+    Draw(DrawError),
+    DrawIndexed(DrawIndexedError),
+    DebugMarker(DebugMarkerError),
+    EndRenderPass(AutoCommandBufferBuilderContextError),
+    BuildCommandBuffer(BuildError),
+    Execute(CommandBufferExecError),
+);
+
+impl From<AcquireError> for Error {
+    fn from(e: AcquireError) -> Self {
+        match e {
+            AcquireError::DeviceLost => Self::DeviceLost,
+            e => Self::Acquire(e),
+        }
+    }
+}
+
+impl From<FlushError> for Error {
+    fn from(e: FlushError) -> Self {
+        match e {
+            FlushError::DeviceLost => Self::DeviceLost,
+            e => Self::Flush(e),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use egui::{Key, Modifiers, PointerButton, Pos2, Vec2};
+use winit::event::{
+    ElementState, ModifiersState, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent,
+};
+
+/// Points scrolled per line of a mouse wheel.
+const POINTS_PER_LINE: f32 = 50.0;
+
+/// Window input collected between two UI frames.
+#[derive(Default)]
+pub struct Input {
+    pub events: Vec<egui::Event>,
+    pub modifiers: Modifiers,
+    pointer: Option<Pos2>,
+}
+
+impl Input {
+    /// Translates `event`, positions are converted from physical pixels with
+    /// `pixels_per_point`.
+    pub fn push(&mut self, event: &WindowEvent, pixels_per_point: f32) {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                let pos = Pos2::new(
+                    position.x as f32 / pixels_per_point,
+                    position.y as f32 / pixels_per_point,
+                );
+                self.pointer = Some(pos);
+                self.events.push(egui::Event::PointerMoved(pos));
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.pointer = None;
+                self.events.push(egui::Event::PointerGone);
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                if let (Some(pos), Some(button)) = (self.pointer, get_button(*button)) {
+                    self.events.push(egui::Event::PointerButton {
+                        pos,
+                        button,
+                        pressed: *state == ElementState::Pressed,
+                        modifiers: self.modifiers,
+                    });
+                }
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let delta = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => Vec2::new(*x, *y) * POINTS_PER_LINE,
+                    MouseScrollDelta::PixelDelta(delta) => {
+                        Vec2::new(delta.x as f32, delta.y as f32) / pixels_per_point
+                    }
+                };
+                self.events.push(egui::Event::Scroll(delta));
+            }
+            // NOTE: control characters arrive as keys
+            WindowEvent::ReceivedCharacter(c)
+                if !c.is_control() && !self.modifiers.ctrl && !self.modifiers.mac_cmd =>
+            {
+                self.events.push(egui::Event::Text(c.to_string()));
+            }
+            WindowEvent::KeyboardInput { input, .. } => {
+                if let Some(key) = input.virtual_keycode.and_then(get_key) {
+                    self.events.push(egui::Event::Key {
+                        key,
+                        pressed: input.state == ElementState::Pressed,
+                        repeat: false,
+                        modifiers: self.modifiers,
+                    });
+                }
+            }
+            WindowEvent::ModifiersChanged(state) => self.modifiers = get_modifiers(*state),
+            _ => {}
+        }
+    }
+}
+
+fn get_modifiers(state: ModifiersState) -> Modifiers {
+    let mac = cfg!(target_os = "macos");
+    Modifiers {
+        alt: state.alt(),
+        ctrl: state.ctrl(),
+        shift: state.shift(),
+        mac_cmd: mac && state.logo(),
+        command: if mac { state.logo() } else { state.ctrl() },
+    }
+}
+
+fn get_button(button: MouseButton) -> Option<PointerButton> {
+    match button {
+        MouseButton::Left => Some(PointerButton::Primary),
+        MouseButton::Right => Some(PointerButton::Secondary),
+        MouseButton::Middle => Some(PointerButton::Middle),
+        MouseButton::Other(_) => None,
+    }
+}
+
+/// Keys used for navigating and editing text, other keys only arrive as
+/// text.
+fn get_key(key: VirtualKeyCode) -> Option<Key> {
+    Some(match key {
+        VirtualKeyCode::Down => Key::ArrowDown,
+        VirtualKeyCode::Left => Key::ArrowLeft,
+        VirtualKeyCode::Right => Key::ArrowRight,
+        VirtualKeyCode::Up => Key::ArrowUp,
+        VirtualKeyCode::Escape => Key::Escape,
+        VirtualKeyCode::Tab => Key::Tab,
+        VirtualKeyCode::Back => Key::Backspace,
+        VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => Key::Enter,
+        VirtualKeyCode::Space => Key::Space,
+        VirtualKeyCode::Insert => Key::Insert,
+        VirtualKeyCode::Delete => Key::Delete,
+        VirtualKeyCode::Home => Key::Home,
+        VirtualKeyCode::End => Key::End,
+        VirtualKeyCode::PageUp => Key::PageUp,
+        VirtualKeyCode::PageDown => Key::PageDown,
+        VirtualKeyCode::A => Key::A,
+        VirtualKeyCode::C => Key::C,
+        VirtualKeyCode::K => Key::K,
+        VirtualKeyCode::U => Key::U,
+        VirtualKeyCode::V => Key::V,
+        VirtualKeyCode::W => Key::W,
+        VirtualKeyCode::X => Key::X,
+        VirtualKeyCode::Y => Key::Y,
+        VirtualKeyCode::Z => Key::Z,
+        _ => return None,
+    })
+}
@@ -0,0 +1,294 @@
+use std::{collections::HashMap, sync::Arc, time::Instant};
+
+use egui::{epaint::Primitive, ClippedPrimitive, Context, ImageData, TextureId, TexturesDelta};
+use vulkano::{
+    buffer::{BufferUsage, CpuBufferPool},
+    descriptor::{descriptor_set::PersistentDescriptorSet, DescriptorSet, PipelineLayoutAbstract},
+    format::Format,
+    image::{view::ImageView, ImageAccess, ImageDimensions, ImmutableImage, MipmapsCount},
+    pipeline::viewport::Scissor,
+    render_pass::RenderPass,
+    sampler::{Filter, MipmapMode, Sampler, SamplerAddressMode},
+    sync::GpuFuture,
+};
+use winit::event::WindowEvent;
+
+use crate::device::{set_debug_name, LogicalDevice, PoolName};
+use crate::error::Result;
+use crate::render::{OutputTransfer, PassContext};
+
+mod input;
+pub mod panels;
+mod pipeline;
+
+pub use panels::FrameStats;
+
+/// Corner of a UI triangle.
+#[derive(Default, Clone, Copy)]
+pub struct GuiVertex {
+    position: [f32; 2],
+    uv: [f32; 2],
+    color: [f32; 4],
+}
+
+vulkano::impl_vertex!(GuiVertex, position, uv, color);
+
+/// A texture egui manages, kept on the CPU as well so partial updates can be
+/// applied before it is uploaded again.
+struct Texture {
+    size: [usize; 2],
+    pixels: Vec<[u8; 4]>,
+    set: Arc<dyn DescriptorSet + Send + Sync>,
+}
+
+/// An egui context of one window, fed with its events and drawn in the
+/// overlay pass. There is no clipboard.
+pub struct Gui {
+    context: Context,
+    input: input::Input,
+    start: Instant,
+    /// Triangles of the last UI frame, drawn until the next one.
+    primitives: Vec<ClippedPrimitive>,
+    /// Textures the last UI frame stopped using.
+    unused_textures: Vec<TextureId>,
+    textures: HashMap<TextureId, Texture>,
+    pipeline: Arc<pipeline::GuiPipeline>,
+    linear_sampler: Arc<Sampler>,
+    nearest_sampler: Arc<Sampler>,
+    vertex_pool: CpuBufferPool<GuiVertex>,
+    vertex_name: PoolName,
+    index_pool: CpuBufferPool<u32>,
+    index_name: PoolName,
+    output_transfer: OutputTransfer,
+}
+
+fn get_sampler(logical_device: &LogicalDevice, filter: Filter) -> Result<Arc<Sampler>> {
+    Ok(Sampler::new(
+        logical_device.device.clone(),
+        filter,
+        filter,
+        MipmapMode::Nearest,
+        SamplerAddressMode::ClampToEdge,
+        SamplerAddressMode::ClampToEdge,
+        SamplerAddressMode::ClampToEdge,
+        0.0,
+        1.0,
+        0.0,
+        0.0,
+    )?)
+}
+
+impl Gui {
+    pub fn new(
+        logical_device: &LogicalDevice,
+        renderpass: &Arc<RenderPass>,
+        output_transfer: OutputTransfer,
+    ) -> Result<Self> {
+        let device = &logical_device.device;
+        Ok(Self {
+            context: Context::default(),
+            input: input::Input::default(),
+            start: Instant::now(),
+            primitives: vec![],
+            unused_textures: vec![],
+            textures: HashMap::new(),
+            pipeline: pipeline::get_pipeline(device, renderpass)?,
+            linear_sampler: get_sampler(logical_device, Filter::Linear)?,
+            nearest_sampler: get_sampler(logical_device, Filter::Nearest)?,
+            vertex_pool: CpuBufferPool::vertex_buffer(device.clone()),
+            vertex_name: PoolName::new("gui vertices"),
+            index_pool: CpuBufferPool::new(device.clone(), BufferUsage::index_buffer()),
+            index_name: PoolName::new("gui indices"),
+            output_transfer,
+        })
+    }
+
+    pub fn get_context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Queues a window event for the next UI frame.
+    pub fn on_event(&mut self, event: &WindowEvent, scale_factor: f64) {
+        self.input.push(event, scale_factor as f32);
+    }
+
+    /// The pointer is over the UI or dragging something in it.
+    pub fn wants_pointer_input(&self) -> bool {
+        self.context.wants_pointer_input()
+    }
+
+    /// A text field has focus.
+    pub fn wants_keyboard_input(&self) -> bool {
+        self.context.wants_keyboard_input()
+    }
+
+    /// Runs one UI frame over a window of `dimensions` physical pixels and
+    /// uploads the textures it changed.
+    pub fn run(
+        &mut self,
+        logical_device: &LogicalDevice,
+        dimensions: [u32; 2],
+        scale_factor: f64,
+        build: impl FnOnce(&Context),
+    ) -> Result<()> {
+        let pixels_per_point = scale_factor as f32;
+        let size = egui::vec2(dimensions[0] as f32, dimensions[1] as f32) / pixels_per_point;
+        let raw_input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, size)),
+            pixels_per_point: Some(pixels_per_point),
+            time: Some(self.start.elapsed().as_secs_f64()),
+            modifiers: self.input.modifiers,
+            events: std::mem::take(&mut self.input.events),
+            focused: true,
+            ..egui::RawInput::default()
+        };
+        let output = self.context.run(raw_input, build);
+        for id in std::mem::take(&mut self.unused_textures) {
+            self.textures.remove(&id);
+        }
+        self.update_textures(logical_device, output.textures_delta)?;
+        self.primitives = self.context.tessellate(output.shapes);
+        Ok(())
+    }
+
+    fn update_textures(
+        &mut self,
+        logical_device: &LogicalDevice,
+        delta: TexturesDelta,
+    ) -> Result<()> {
+        for (id, image_delta) in delta.set {
+            let size = image_delta.image.size();
+            let pixels: Vec<[u8; 4]> = match &image_delta.image {
+                ImageData::Color(image) => image.pixels.iter().map(|c| c.to_array()).collect(),
+                ImageData::Font(image) => image.srgba_pixels(None).map(|c| c.to_array()).collect(),
+            };
+            let (size, pixels) = match (image_delta.pos, self.textures.remove(&id)) {
+                (Some([x, y]), Some(mut texture)) => {
+                    for row in 0..size[1] {
+                        let start = (y + row) * texture.size[0] + x;
+                        texture.pixels[start..start + size[0]]
+                            .copy_from_slice(&pixels[row * size[0]..(row + 1) * size[0]]);
+                    }
+                    (texture.size, texture.pixels)
+                }
+                (Some(_), None) => {
+                    log::warn!("skipping partial update of unknown gui texture {:?}", id);
+                    continue;
+                }
+                (None, _) => (size, pixels),
+            };
+            let (image, upload) = ImmutableImage::from_iter(
+                pixels.iter().copied(),
+                ImageDimensions::Dim2d {
+                    width: size[0] as u32,
+                    height: size[1] as u32,
+                    array_layers: 1,
+                },
+                MipmapsCount::One,
+                Format::R8G8B8A8Srgb,
+                logical_device.graphical_queue.clone(),
+            )?;
+            upload.then_signal_fence_and_flush()?.wait(None)?;
+            set_debug_name(
+                &logical_device.device,
+                image.inner().image,
+                &format!("gui texture {:?}", id),
+            );
+            let sampler = match image_delta.options.magnification {
+                egui::TextureFilter::Nearest => self.nearest_sampler.clone(),
+                egui::TextureFilter::Linear => self.linear_sampler.clone(),
+            };
+            let layout = self
+                .pipeline
+                .descriptor_set_layout(0)
+                .expect("gui shader uses set 0")
+                .clone();
+            let set = PersistentDescriptorSet::start(layout)
+                .add_image(ImageView::new(image)?)?
+                .add_sampler(sampler)?
+                .build()?;
+            self.textures.insert(
+                id,
+                Texture {
+                    size,
+                    pixels,
+                    set: Arc::new(set),
+                },
+            );
+        }
+        self.unused_textures = delta.free;
+        Ok(())
+    }
+
+    /// Draws the last UI frame, meshes are clipped to their rectangles.
+    pub fn render(&self, pass: &mut PassContext) -> Result<()> {
+        let viewport = match &pass.dynamic_state.viewports {
+            Some(viewports) => viewports[0].dimensions,
+            None => return Ok(()),
+        };
+        let pixels_per_point = self.context.pixels_per_point();
+        let push_data = crate::shaders::gui_vs::ty::PushConstantData {
+            screen_size: [
+                viewport[0] / pixels_per_point,
+                viewport[1] / pixels_per_point,
+            ],
+            output_transfer: self.output_transfer as u32,
+        };
+        let mut dynamic_state = pass.dynamic_state.clone();
+        for ClippedPrimitive {
+            clip_rect,
+            primitive,
+        } in &self.primitives
+        {
+            let mesh = match primitive {
+                Primitive::Mesh(mesh) if !mesh.is_empty() => mesh,
+                // NOTE: paint callbacks are not supported
+                _ => continue,
+            };
+            let texture = match self.textures.get(&mesh.texture_id) {
+                Some(texture) => texture,
+                None => continue,
+            };
+            let min = (clip_rect.min.to_vec2() * pixels_per_point).round();
+            let max = (clip_rect.max.to_vec2() * pixels_per_point).round();
+            let min = [min.x.clamp(0.0, viewport[0]), min.y.clamp(0.0, viewport[1])];
+            let max = [
+                max.x.clamp(min[0], viewport[0]),
+                max.y.clamp(min[1], viewport[1]),
+            ];
+            if max[0] <= min[0] || max[1] <= min[1] {
+                continue;
+            }
+            dynamic_state.scissors = Some(vec![Scissor {
+                origin: [min[0] as i32, min[1] as i32],
+                dimensions: [(max[0] - min[0]) as u32, (max[1] - min[1]) as u32],
+            }]);
+            let vertices: Vec<GuiVertex> = mesh
+                .vertices
+                .iter()
+                .map(|vertex| {
+                    let [r, g, b, a] = vertex.color.to_array();
+                    GuiVertex {
+                        position: [vertex.pos.x, vertex.pos.y],
+                        uv: [vertex.uv.x, vertex.uv.y],
+                        color: [r, g, b, a].map(|c| c as f32 / 255.0),
+                    }
+                })
+                .collect();
+            let vertex_buffer = self.vertex_pool.chunk(vertices)?;
+            let index_buffer = self.index_pool.chunk(mesh.indices.iter().copied())?;
+            self.vertex_name.apply(&vertex_buffer);
+            self.index_name.apply(&index_buffer);
+            pass.cmd_builder.draw_indexed(
+                self.pipeline.clone(),
+                &dynamic_state,
+                vertex_buffer,
+                index_buffer,
+                texture.set.clone(),
+                push_data,
+                vec![],
+            )?;
+        }
+        Ok(())
+    }
+}
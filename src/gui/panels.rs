@@ -0,0 +1,181 @@
+use egui::{ComboBox, DragValue, Slider, Ui};
+
+use crate::camera::Camera;
+use crate::light::DirectionalLight;
+use crate::model::GameObject;
+use crate::postprocess::{PostChain, PostEffect};
+use crate::render::{PresentModeConfig, Render};
+use crate::shadow::ShadowMapper;
+use crate::simple_display::CullStats;
+
+/// Measurements of a view's last frame.
+#[derive(Default, Clone, Copy)]
+pub struct FrameStats {
+    /// Seconds between frames, smoothed over the last few.
+    pub frame_time: f32,
+    /// Summed over every `Frame::draw_game_objects` in the scene pass.
+    pub cull: CullStats,
+}
+
+fn drag_values(ui: &mut Ui, label: &str, values: &mut [f32], speed: f32) {
+    ui.horizontal(|ui| {
+        for component in values.iter_mut() {
+            ui.add(DragValue::new(component).speed(speed));
+        }
+        ui.label(label);
+    });
+}
+
+pub fn frame_stats(ui: &mut Ui, stats: &FrameStats) {
+    let fps = if stats.frame_time > 0.0 {
+        1.0 / stats.frame_time
+    } else {
+        0.0
+    };
+    ui.label(format!(
+        "{:.2} ms ({:.0} fps)",
+        stats.frame_time * 1000.0,
+        fps
+    ));
+    ui.label(format!(
+        "objects drawn: {}, culled: {}",
+        stats.cull.drawn, stats.cull.culled
+    ));
+}
+
+/// The far plane is only editable without reverse depth, which has none.
+pub fn camera(ui: &mut Ui, camera: &mut Camera) {
+    ui.horizontal(|ui| {
+        ui.drag_angle(&mut camera.fov_y);
+        ui.label("field of view");
+    });
+    camera.fov_y = camera.fov_y.clamp(0.1, 3.0);
+    ui.add(
+        DragValue::new(&mut camera.near)
+            .speed(0.01)
+            .clamp_range(0.001..=camera.far)
+            .prefix("near: "),
+    );
+    ui.add_enabled(
+        !camera.reverse_z,
+        DragValue::new(&mut camera.far)
+            .speed(0.1)
+            .clamp_range(camera.near..=f32::MAX)
+            .prefix("far: "),
+    );
+}
+
+pub fn light(ui: &mut Ui, light: &mut DirectionalLight) {
+    drag_values(ui, "direction", &mut light.direction, 0.01);
+    ui.add(Slider::new(&mut light.ambient, 0.0..=1.0).text("ambient"));
+}
+
+/// Swapchain settings, changes recreate the swapchain on the next frame.
+/// HDR output, MSAA and the frames in flight are only shown, they are baked
+/// into render passes and pipelines when the view is created.
+pub fn renderer(ui: &mut Ui, render: &mut Render) {
+    let [width, height] = render.get_dimensions();
+    ui.label(format!(
+        "{}x{} at scale {:.2}",
+        width,
+        height,
+        render.get_scale_factor()
+    ));
+    ui.label(format!("surface: {:?}", render.get_surface_format()));
+    let mut config = render.get_swapchain_config();
+    ui.label(format!("hdr: {:?}", config.hdr));
+    let present_mode = config.present_mode;
+    ComboBox::from_label("present mode")
+        .selected_text(format!("{:?}", config.present_mode))
+        .show_ui(ui, |ui| {
+            for mode in [
+                PresentModeConfig::Vsync,
+                PresentModeConfig::Adaptive,
+                PresentModeConfig::Mailbox,
+                PresentModeConfig::Immediate,
+            ] {
+                ui.selectable_value(&mut config.present_mode, mode, format!("{:?}", mode));
+            }
+        });
+    if config.present_mode != present_mode {
+        render.set_swapchain_config(config);
+    }
+}
+
+/// Bias, filtering and cascade splits, the resolution and cascade count are
+/// fixed.
+pub fn shadows(ui: &mut Ui, shadow_mapper: &mut ShadowMapper) {
+    let mut config = shadow_mapper.get_config();
+    ui.add(
+        DragValue::new(&mut config.depth_bias)
+            .speed(0.0001)
+            .clamp_range(0.0..=0.1)
+            .prefix("depth bias: "),
+    );
+    ui.add(
+        DragValue::new(&mut config.normal_bias)
+            .speed(0.001)
+            .clamp_range(0.0..=1.0)
+            .prefix("normal bias: "),
+    );
+    ui.add(Slider::new(&mut config.pcf_radius, 0..=4).text("pcf radius"));
+    ui.add(Slider::new(&mut config.split_lambda, 0.0..=1.0).text("split lambda"));
+    shadow_mapper.set_config(config);
+}
+
+/// Settings of every effect in chain order, the LUT and custom shaders are
+/// fixed.
+pub fn post_effects(ui: &mut Ui, post_chain: &mut PostChain) {
+    let effects: Vec<PostEffect> = post_chain.get_effects().cloned().collect();
+    for (index, mut effect) in effects.into_iter().enumerate() {
+        let before = effect.clone();
+        ui.push_id(index, |ui| match &mut effect {
+            PostEffect::Tonemap { exposure } => {
+                ui.add(Slider::new(exposure, 0.0..=8.0).text("tonemap exposure"));
+            }
+            PostEffect::Gamma { gamma } => {
+                ui.add(Slider::new(gamma, 0.1..=4.0).text("gamma"));
+            }
+            PostEffect::Vignette { intensity, radius } => {
+                ui.add(Slider::new(intensity, 0.0..=1.0).text("vignette intensity"));
+                ui.add(Slider::new(radius, 0.0..=1.0).text("vignette radius"));
+            }
+            PostEffect::Fxaa { span } => {
+                ui.add(Slider::new(span, 1.0..=16.0).text("fxaa span"));
+            }
+            PostEffect::ColorGrading { strength, .. } => {
+                ui.add(Slider::new(strength, 0.0..=1.0).text("grading strength"));
+            }
+            PostEffect::Custom(custom) => {
+                drag_values(ui, custom.name, &mut custom.params, 0.01);
+            }
+        });
+        if effect != before {
+            if let Err(e) = post_chain.set_effect(index, effect) {
+                log::warn!("failed to update post effect {}: {}", index, e);
+            }
+        }
+    }
+}
+
+/// Transform and look of every object, one collapsible section each.
+pub fn game_objects(ui: &mut Ui, game_objects: &mut [GameObject]) {
+    for obj in game_objects {
+        ui.collapsing(format!("object {}", obj.get_id()), |ui| {
+            drag_values(ui, "translate", &mut obj.translate, 0.05);
+            drag_values(ui, "scale", &mut obj.scale, 0.01);
+            ui.horizontal(|ui| {
+                for angle in obj.rotate.iter_mut() {
+                    ui.drag_angle(angle);
+                }
+                ui.label("rotate");
+            });
+            ui.horizontal(|ui| {
+                ui.color_edit_button_rgb(&mut obj.tint);
+                ui.label("tint");
+            });
+            ui.add(Slider::new(&mut obj.alpha, 0.0..=1.0).text("alpha"));
+            ui.checkbox(&mut obj.cast_shadows, "cast shadows");
+        });
+    }
+}
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use vulkano::descriptor::PipelineLayoutAbstract;
+use vulkano::device::Device;
+use vulkano::pipeline::blend::{AttachmentBlend, BlendFactor};
+use vulkano::pipeline::{vertex::SingleBufferDefinition, GraphicsPipeline};
+use vulkano::render_pass::RenderPass;
+
+use super::GuiVertex;
+use crate::device::set_debug_name;
+use crate::error::Result;
+use crate::render::graph::get_subpass;
+use crate::shaders::{gui_fs, gui_vs};
+
+pub type GuiPipeline = GraphicsPipeline<
+    SingleBufferDefinition<GuiVertex>,
+    Box<dyn PipelineLayoutAbstract + Send + Sync + 'static>,
+>;
+
+/// Meshes are clipped with a dynamic scissor, egui does not keep a winding
+/// order so nothing is culled.
+pub fn get_pipeline(
+    device: &Arc<Device>,
+    renderpass: &Arc<RenderPass>,
+) -> Result<Arc<GuiPipeline>> {
+    let vs = gui_vs::Shader::load(device.clone())?;
+    let fs = gui_fs::Shader::load(device.clone())?;
+
+    let pipeline = Arc::new(
+        GraphicsPipeline::start()
+            .vertex_input_single_buffer::<GuiVertex>()
+            .vertex_shader(vs.main_entry_point(), ())
+            .triangle_list()
+            .viewports_scissors_dynamic(1)
+            .fragment_shader(fs.main_entry_point(), ())
+            .blend_collective(AttachmentBlend {
+                color_source: BlendFactor::One,
+                alpha_source: BlendFactor::One,
+                ..AttachmentBlend::alpha_blending()
+            })
+            .render_pass(get_subpass(renderpass)?)
+            .build(device.clone())?,
+    );
+    set_debug_name(device, &*pipeline, "gui pipeline");
+    Ok(pipeline)
+}
@@ -4,48 +4,46 @@ use vulkano::{app_info_from_cargo_toml, instance::layers_list};
 
 use std::sync::Arc;
 
-#[cfg(all(debug_assertions))]
-pub const ENABLE_VALIDATION_LAYERS: bool = true;
-#[cfg(not(debug_assertions))]
-pub const ENABLE_VALIDATION_LAYERS: bool = false;
+use crate::device::ValidationConfig;
+use crate::error::Result;
 
 const VALIDATION_LAYERS: &[&str] = &[
     "VK_LAYER_KHRONOS_validation",
     "VK_LAYER_LUNARG_standard_validation",
 ];
 
-pub fn create_instance() -> Arc<Instance> {
+/// Creates the instance, with the validation layers when `validation` asks
+/// for them and they are installed.
+pub fn create_instance(validation: &ValidationConfig) -> Result<Arc<Instance>> {
+    let layers = if validation.enabled {
+        check_validation_layer_support()?
+    } else {
+        vec![]
+    };
+    let supported_extensions =
+        InstanceExtensions::supported_by_core().unwrap_or_else(|_| InstanceExtensions::none());
     let required_extensions = InstanceExtensions {
-        ext_debug_utils: ENABLE_VALIDATION_LAYERS,
+        ext_debug_utils: !layers.is_empty(),
+        // NOTE: this is to allow HDR swapchain color spaces
+        ext_swapchain_colorspace: supported_extensions.ext_swapchain_colorspace,
         ..vulkano_win::required_extensions()
     };
     let app_info = app_info_from_cargo_toml!();
-    if ENABLE_VALIDATION_LAYERS {
-        Instance::new(
-            Some(&app_info),
-            &required_extensions,
-            check_validation_layer_support().into_iter(),
-        )
-        .expect("failed to create Vulkan instance")
-    } else {
-        Instance::new(Some(&app_info), &required_extensions, None)
-            .expect("failed to create Vulkan instance")
-    }
+    let instance = Instance::new(Some(&app_info), &required_extensions, layers)?;
+    Ok(instance)
 }
 
-fn check_validation_layer_support() -> Vec<&'static str> {
-    let layers: Vec<_> = layers_list()
-        .unwrap()
-        .map(|l| l.name().to_owned())
-        .collect();
-    dbg!(&layers);
+/// Installed validation layers, empty with a warning when there are none.
+fn check_validation_layer_support() -> Result<Vec<&'static str>> {
+    let layers: Vec<_> = layers_list()?.map(|l| l.name().to_owned()).collect();
+    log::debug!("available layers: {:?}", layers);
     let validation_layers: Vec<_> = VALIDATION_LAYERS
         .iter()
         .filter(|layer_name| layers.contains(&layer_name.to_string()))
         .cloned()
         .collect();
     if validation_layers.is_empty() {
-        panic!("no validation extension found!");
+        log::warn!("no validation layer found, continuing without validation");
     }
-    validation_layers
+    Ok(validation_layers)
 }
@@ -0,0 +1,23 @@
+pub mod app;
+pub mod camera;
+pub mod debug_draw;
+pub mod device;
+pub mod error;
+pub mod gui;
+pub mod instance;
+pub mod light;
+pub mod logger;
+pub mod model;
+pub mod postprocess;
+pub mod render;
+mod shaders;
+pub mod shadow;
+pub mod simple_display;
+pub mod text;
+pub mod view;
+pub mod window;
+
+pub use app::{run, App, AppConfig, Engine};
+pub use egui;
+pub use error::{Error, Result};
+pub use view::Frame;
@@ -0,0 +1,34 @@
+use glam::Vec3;
+
+/// Light from infinitely far away, like the sun, shading lit pipelines.
+#[derive(Clone, Copy, Debug)]
+pub struct DirectionalLight {
+    /// Direction the light travels in, does not need to be normalized. A zero
+    /// vector falls back to the default direction.
+    pub direction: [f32; 3],
+    /// Fraction of the color kept where the light does not reach.
+    pub ambient: f32,
+}
+
+// NOTE: world +y is down on screen
+const DEFAULT_DIRECTION: [f32; 3] = [0.3, 1.0, 0.5];
+
+impl Default for DirectionalLight {
+    fn default() -> Self {
+        Self {
+            direction: DEFAULT_DIRECTION,
+            ambient: 0.25,
+        }
+    }
+}
+
+impl DirectionalLight {
+    pub fn get_direction(&self) -> Vec3 {
+        let direction = Vec3::from(self.direction).normalize_or_zero();
+        if direction == Vec3::ZERO {
+            Vec3::from(DEFAULT_DIRECTION).normalize()
+        } else {
+            direction
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// Writes records to stderr as `LEVEL target: message`.
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!(
+                "{:<5} {}: {}",
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+/// Parses `error`, `warn`, `info`, `debug`, `trace` or `off`.
+pub fn parse_level(value: &str) -> Option<LevelFilter> {
+    value.parse().ok()
+}
+
+/// Installs the stderr logger, the level is read from `HEX_LOG` and defaults
+/// to `info`.
+pub fn init() {
+    let level = std::env::var("HEX_LOG")
+        .ok()
+        .and_then(|value| parse_level(&value))
+        .unwrap_or(LevelFilter::Info);
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+}
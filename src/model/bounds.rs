@@ -0,0 +1,187 @@
+use glam::{Mat4, Vec3, Vec4};
+
+/// Axis aligned bounding box.
+#[derive(Clone, Copy, Debug)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Self {
+        let mut points = points.into_iter();
+        let first = points.next().unwrap_or(Vec3::ZERO);
+        points.fold(
+            Self {
+                min: first,
+                max: first,
+            },
+            |aabb, point| Self {
+                min: aabb.min.min(point),
+                max: aabb.max.max(point),
+            },
+        )
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn corners(&self) -> [Vec3; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            Vec3::new(min.x, min.y, min.z),
+            Vec3::new(max.x, min.y, min.z),
+            Vec3::new(min.x, max.y, min.z),
+            Vec3::new(max.x, max.y, min.z),
+            Vec3::new(min.x, min.y, max.z),
+            Vec3::new(max.x, min.y, max.z),
+            Vec3::new(min.x, max.y, max.z),
+            Vec3::new(max.x, max.y, max.z),
+        ]
+    }
+
+    /// Box enclosing this box after it is moved by `transform`.
+    pub fn transform(&self, transform: &Mat4) -> Self {
+        Self::from_points(
+            self.corners()
+                .iter()
+                .map(|corner| transform.transform_point3(*corner)),
+        )
+    }
+}
+
+/// Bounding sphere, cheaper to test than the box but looser.
+#[derive(Clone, Copy, Debug)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn from_points(points: impl IntoIterator<Item = Vec3> + Clone) -> Self {
+        let center = Aabb::from_points(points.clone()).center();
+        let radius = points
+            .into_iter()
+            .map(|point| point.distance(center))
+            .fold(0.0, f32::max);
+        Self { center, radius }
+    }
+
+    /// Sphere enclosing this sphere after it is moved by `transform`.
+    pub fn transform(&self, transform: &Mat4) -> Self {
+        let scale = transform
+            .x_axis
+            .truncate()
+            .length()
+            .max(transform.y_axis.truncate().length())
+            .max(transform.z_axis.truncate().length());
+        Self {
+            center: transform.transform_point3(self.center),
+            radius: self.radius * scale,
+        }
+    }
+}
+
+/// Camera frustum as six inward facing planes (`xyz` normal, `w` distance).
+pub struct Frustum {
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes of a view projection matrix with a `0..1` depth range.
+    pub fn from_matrix(view_proj: &Mat4) -> Self {
+        let (x, y, z, w) = (
+            view_proj.row(0),
+            view_proj.row(1),
+            view_proj.row(2),
+            view_proj.row(3),
+        );
+        Self {
+            planes: [w + x, w - x, w + y, w - y, z, w - z].map(Self::normalize),
+        }
+    }
+
+    fn normalize(plane: Vec4) -> Vec4 {
+        let length = plane.truncate().length();
+        if length > f32::EPSILON {
+            plane / length
+        } else {
+            // NOTE: infinite far plane, nothing lies beyond it
+            Vec4::new(0.0, 0.0, 0.0, f32::INFINITY)
+        }
+    }
+
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.truncate().dot(sphere.center) + plane.w >= -sphere.radius)
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            let normal = plane.truncate();
+            let positive = Vec3::select(normal.cmpge(Vec3::ZERO), aabb.max, aabb.min);
+            normal.dot(positive) + plane.w >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_frustum() -> Frustum {
+        Frustum::from_matrix(&Mat4::perspective_lh(1.0, 1.0, 0.1, 100.0))
+    }
+
+    fn sphere(center: Vec3, radius: f32) -> BoundingSphere {
+        BoundingSphere { center, radius }
+    }
+
+    fn cube(center: Vec3, half: f32) -> Aabb {
+        Aabb {
+            min: center - Vec3::splat(half),
+            max: center + Vec3::splat(half),
+        }
+    }
+
+    #[test]
+    fn sphere_inside_and_outside() {
+        let frustum = get_frustum();
+        assert!(frustum.intersects_sphere(&sphere(Vec3::new(0.0, 0.0, 10.0), 1.0)));
+        assert!(!frustum.intersects_sphere(&sphere(Vec3::new(0.0, 0.0, -10.0), 1.0)));
+        assert!(!frustum.intersects_sphere(&sphere(Vec3::new(0.0, 0.0, 200.0), 1.0)));
+        assert!(!frustum.intersects_sphere(&sphere(Vec3::new(50.0, 0.0, 10.0), 1.0)));
+    }
+
+    #[test]
+    fn sphere_crossing_a_plane_intersects() {
+        let frustum = get_frustum();
+        assert!(frustum.intersects_sphere(&sphere(Vec3::new(0.0, 0.0, 101.0), 2.0)));
+        assert!(frustum.intersects_sphere(&sphere(Vec3::new(6.0, 0.0, 10.0), 2.0)));
+    }
+
+    #[test]
+    fn aabb_inside_and_outside() {
+        let frustum = get_frustum();
+        assert!(frustum.intersects_aabb(&cube(Vec3::new(0.0, 0.0, 10.0), 1.0)));
+        assert!(!frustum.intersects_aabb(&cube(Vec3::new(0.0, 0.0, -10.0), 1.0)));
+        assert!(!frustum.intersects_aabb(&cube(Vec3::new(0.0, 50.0, 10.0), 1.0)));
+        assert!(frustum.intersects_aabb(&cube(Vec3::new(6.0, 0.0, 10.0), 1.5)));
+    }
+
+    #[test]
+    fn aabb_around_the_camera_intersects() {
+        assert!(get_frustum().intersects_aabb(&cube(Vec3::ZERO, 1000.0)));
+    }
+
+    #[test]
+    fn infinite_far_plane_culls_nothing_far_away() {
+        let frustum = Frustum::from_matrix(&Mat4::perspective_infinite_reverse_lh(1.0, 1.0, 0.1));
+        let far = Vec3::new(0.0, 0.0, 1.0e6);
+        assert!(frustum.intersects_sphere(&sphere(far, 1.0)));
+        assert!(frustum.intersects_aabb(&cube(far, 1.0)));
+        assert!(!frustum.intersects_sphere(&sphere(-far, 1.0)));
+    }
+}
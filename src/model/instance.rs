@@ -0,0 +1,34 @@
+/// Per-instance attributes fed to the instanced pipeline through a second vertex buffer.
+#[derive(Default, Clone, Copy)]
+pub struct InstanceData {
+    model_x: [f32; 4],
+    model_y: [f32; 4],
+    model_z: [f32; 4],
+    model_w: [f32; 4],
+    tint: [f32; 4],
+    custom: [i32; 2],
+}
+
+vulkano::impl_vertex!(
+    InstanceData,
+    model_x,
+    model_y,
+    model_z,
+    model_w,
+    tint,
+    custom
+);
+
+impl InstanceData {
+    pub fn new(model: glam::Mat4, tint: [f32; 4], custom: [i32; 2]) -> Self {
+        let [model_x, model_y, model_z, model_w] = model.to_cols_array_2d();
+        Self {
+            model_x,
+            model_y,
+            model_z,
+            model_w,
+            tint,
+            custom,
+        }
+    }
+}
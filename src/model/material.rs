@@ -0,0 +1,58 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+// NOTE: process wide, batching treats equal ids as the same material
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// How a material's color is combined with what is already drawn. Opaque
+/// objects are drawn first, front to back, then the others back to front
+/// without writing depth.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    Opaque,
+    /// Mixes by the object's alpha.
+    Alpha,
+    /// Adds the color scaled by alpha, for glows and highlights.
+    Additive,
+    /// Color already multiplied by alpha.
+    Premultiplied,
+}
+
+impl BlendMode {
+    pub const ALL: [Self; 4] = [
+        Self::Opaque,
+        Self::Alpha,
+        Self::Additive,
+        Self::Premultiplied,
+    ];
+
+    pub fn is_transparent(self) -> bool {
+        self != Self::Opaque
+    }
+}
+
+/// Shading state shared by a group of `GameObject`s; objects are only batched
+/// together when they use the same material.
+pub struct Material {
+    id: usize,
+    blend: BlendMode,
+}
+
+impl Material {
+    pub fn new() -> Arc<Self> {
+        Self::with_blend(BlendMode::Opaque)
+    }
+
+    pub fn with_blend(blend: BlendMode) -> Arc<Self> {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        Arc::new(Self { id, blend })
+    }
+
+    pub fn get_id(&self) -> usize {
+        self.id
+    }
+
+    pub fn get_blend(&self) -> BlendMode {
+        self.blend
+    }
+}
@@ -0,0 +1,51 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use vulkano::{
+    buffer::{BufferAccess, CpuAccessibleBuffer},
+    device::Device,
+};
+
+use super::{Aabb, BoundingSphere, Vertex};
+use crate::device::set_debug_name;
+use crate::error::Result;
+
+// NOTE: process wide, meshes loaded on other threads must not share an id
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Vertex data shared by every `GameObject` drawn with the same shape.
+pub struct Mesh {
+    id: usize,
+    pub vertex_buffer: Arc<CpuAccessibleBuffer<[Vertex]>>,
+    pub aabb: Aabb,
+    pub sphere: BoundingSphere,
+}
+
+impl Mesh {
+    pub fn cube(device: &Arc<Device>) -> Result<Arc<Self>> {
+        Self::new(device, Vertex::get_vertex())
+    }
+
+    pub fn new(device: &Arc<Device>, vertices: Vec<Vertex>) -> Result<Arc<Self>> {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let positions = vertices.iter().map(Vertex::position);
+        let aabb = Aabb::from_points(positions.clone());
+        let sphere = BoundingSphere::from_points(positions);
+        let vertex_buffer = Vertex::get_buffer(device, vertices)?;
+        set_debug_name(
+            device,
+            vertex_buffer.inner().buffer,
+            &format!("mesh {} vertices", id),
+        );
+        Ok(Arc::new(Self {
+            id,
+            vertex_buffer,
+            aabb,
+            sphere,
+        }))
+    }
+
+    pub fn get_id(&self) -> usize {
+        self.id
+    }
+}
@@ -1,10 +1,17 @@
 use std::{cell::RefCell, sync::Arc};
 
 use glam::{Mat4, Vec3};
-use vulkano::{buffer::CpuAccessibleBuffer, device::Device};
 
+mod bounds;
+mod instance;
+mod material;
+mod mesh;
 mod vertex;
 
+pub use bounds::{Aabb, BoundingSphere, Frustum};
+pub use instance::InstanceData;
+pub use material::{BlendMode, Material};
+pub use mesh::Mesh;
 pub use vertex::Vertex;
 
 const MAX_DIM: usize = 3;
@@ -12,53 +19,82 @@ type Dims = [f32; MAX_DIM];
 type Rotate = [f32; MAX_DIM];
 
 pub struct GameObject {
-    #[allow(dead_code)]
     id: usize,
-    translate: Dims,
-    scale: Dims,
+    pub translate: Dims,
+    pub scale: Dims,
     pub rotate: Rotate,
-    pub vertex_buffer: Arc<CpuAccessibleBuffer<[Vertex]>>,
+    pub tint: [f32; 3],
+    /// Opacity, only used by materials that blend.
+    pub alpha: f32,
+    pub custom: [i32; 2],
+    /// Drawn into the shadow maps, on by default for opaque materials only
+    /// since shadows have no transparency.
+    pub cast_shadows: bool,
+    pub mesh: Arc<Mesh>,
+    pub material: Arc<Material>,
 }
 
 thread_local! {
-    static OBJECT_COUNT: RefCell<usize> = RefCell::new(0);
+    static OBJECT_COUNT: RefCell<usize> = const { RefCell::new(0) };
 }
 
 impl GameObject {
-    pub fn new(device: &Arc<Device>, translate: Dims, scale: Dims, rotate: Rotate) -> Self {
+    pub fn new(
+        mesh: &Arc<Mesh>,
+        material: &Arc<Material>,
+        translate: Dims,
+        scale: Dims,
+        rotate: Rotate,
+    ) -> Self {
         let id = OBJECT_COUNT.with(|count| {
             let mut game_count = count.borrow_mut();
             let id = *game_count;
             *game_count += 1;
             id
         });
-        let vertex_buffer = Vertex::get_buffer(&device);
         Self {
             id,
-            vertex_buffer,
+            mesh: mesh.clone(),
+            material: material.clone(),
             translate,
             scale,
             rotate,
+            tint: [1.0, 1.0, 1.0],
+            alpha: 1.0,
+            custom: [0, 0],
+            cast_shadows: !material.get_blend().is_transparent(),
         }
     }
 
-    #[allow(dead_code)]
-    fn get_id(&self) -> usize {
+    pub fn get_id(&self) -> usize {
         self.id
     }
 
-    pub fn get_push_data(
-        &self,
-        camera_transform: &Mat4,
-    ) -> crate::shaders::vs::ty::PushConstantData {
+    pub fn get_model_matrix(&self) -> Mat4 {
         let translate = Mat4::from_translation(Vec3::from_slice(&self.translate));
         let rotation = Mat4::from_rotation_y(self.rotate[0]);
         let rotation = rotation * Mat4::from_rotation_x(self.rotate[1]);
         let rotation = rotation * Mat4::from_rotation_z(self.rotate[2]);
         let scale = Mat4::from_scale(Vec3::from_slice(&self.scale));
-        let transform = *camera_transform * translate * scale * rotation;
-        crate::shaders::vs::ty::PushConstantData {
-            transform: Into::<Mat4>::into(transform).to_cols_array_2d(),
-        }
+        translate * scale * rotation
+    }
+
+    pub fn get_instance_data(&self) -> InstanceData {
+        let [r, g, b] = self.tint;
+        InstanceData::new(self.get_model_matrix(), [r, g, b, self.alpha], self.custom)
+    }
+
+    /// Mesh bounds moved into world space, for culling.
+    pub fn get_world_bounds(&self) -> (Aabb, BoundingSphere) {
+        let model = self.get_model_matrix();
+        (
+            self.mesh.aabb.transform(&model),
+            self.mesh.sphere.transform(&model),
+        )
+    }
+
+    /// Objects sharing a key can be drawn with a single instanced draw call.
+    pub fn get_batch_key(&self) -> (usize, usize) {
+        (self.mesh.get_id(), self.material.get_id())
     }
 }
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use vulkano::{
     buffer::{BufferUsage, CpuAccessibleBuffer},
     device::Device,
+    memory::DeviceMemoryAllocError,
 };
 
 #[derive(Default, Clone)]
@@ -13,7 +14,7 @@ pub struct Vertex {
 
 vulkano::impl_vertex!(Vertex, position, color);
 impl Vertex {
-    fn get_vertex() -> Vec<Vertex> {
+    pub fn get_vertex() -> Vec<Vertex> {
         //   o
         //  wgy
         //   r
@@ -172,13 +173,19 @@ impl Vertex {
         ]
     }
 
-    pub fn get_buffer(device: &Arc<Device>) -> Arc<CpuAccessibleBuffer<[Vertex]>> {
+    pub fn position(&self) -> glam::Vec3 {
+        glam::Vec3::from(self.position)
+    }
+
+    pub fn get_buffer(
+        device: &Arc<Device>,
+        vertices: Vec<Vertex>,
+    ) -> Result<Arc<CpuAccessibleBuffer<[Vertex]>>, DeviceMemoryAllocError> {
         CpuAccessibleBuffer::from_iter(
             device.clone(),
             BufferUsage::all(),
             false,
-            Vertex::get_vertex().into_iter(),
+            vertices.into_iter(),
         )
-        .expect("could not create cpu access buffer")
     }
 }
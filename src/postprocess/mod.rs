@@ -0,0 +1,475 @@
+use std::{fmt, sync::Arc};
+
+use vulkano::{
+    descriptor::{descriptor_set::PersistentDescriptorSet, DescriptorSet, PipelineLayoutAbstract},
+    device::Device,
+    format::Format,
+    image::{view::ImageView, ImageAccess, ImageDimensions, ImmutableImage, MipmapsCount},
+    pipeline::vertex::BufferlessVertices,
+    render_pass::RenderPass,
+    sampler::{Filter, MipmapMode, Sampler, SamplerAddressMode},
+    sync::GpuFuture,
+};
+
+use crate::device::{set_debug_name, LogicalDevice};
+use crate::error::{Error, Result};
+use crate::render::graph::{
+    AttachmentInfo, AttachmentSize, AttachmentView, PassDesc, ResourceId, BACKBUFFER,
+};
+use crate::render::{OutputTransfer, PassContext, Render, RenderGraph, SCENE_COLOR, SCENE_PASS};
+
+mod pipeline;
+
+pub use pipeline::{build_pipeline, EffectPipeline};
+
+/// Offscreen target the scene is drawn into when effects are enabled.
+pub const HDR_COLOR: ResourceId = "hdr color";
+pub const HDR_FORMAT: Format = Format::R16G16B16A16Sfloat;
+/// Last pass, writes the chain's result to the swapchain image.
+pub const BLIT_PASS: &str = "blit";
+/// Effect passes in chain order, each also names the target it writes.
+const EFFECT_PASSES: [&str; 8] = [
+    "post 0", "post 1", "post 2", "post 3", "post 4", "post 5", "post 6", "post 7",
+];
+pub const MAX_EFFECTS: usize = EFFECT_PASSES.len();
+
+type LutView = Arc<ImageView<Arc<ImmutableImage>>>;
+
+/// A 3D color lookup table of `size`³ RGBA texels, red varying fastest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lut {
+    size: u32,
+    /// Shared so effects can be cloned and compared cheaply.
+    data: Arc<Vec<[u8; 4]>>,
+}
+
+impl Lut {
+    /// Returns None unless `data` holds `size`³ texels.
+    pub fn new(size: u32, data: Vec<[u8; 4]>) -> Option<Self> {
+        if size < 2 || data.len() != size.pow(3) as usize {
+            return None;
+        }
+        Some(Self {
+            size,
+            data: Arc::new(data),
+        })
+    }
+
+    /// Maps every color to itself, a starting point for grading.
+    pub fn identity(size: u32) -> Self {
+        let size = size.max(2);
+        let to_unorm = |i: u32| (i as f32 / (size - 1) as f32 * 255.0).round() as u8;
+        let data = (0..size.pow(3))
+            .map(|i| {
+                [
+                    to_unorm(i % size),
+                    to_unorm(i / size % size),
+                    to_unorm(i / (size * size)),
+                    255,
+                ]
+            })
+            .collect();
+        Self {
+            size,
+            data: Arc::new(data),
+        }
+    }
+
+    fn upload(&self, logical_device: &LogicalDevice) -> Result<LutView> {
+        let (image, upload) = ImmutableImage::from_iter(
+            self.data.iter().copied(),
+            ImageDimensions::Dim3d {
+                width: self.size,
+                height: self.size,
+                depth: self.size,
+            },
+            MipmapsCount::One,
+            Format::R8G8B8A8Unorm,
+            logical_device.graphical_queue.clone(),
+        )?;
+        upload.then_signal_fence_and_flush()?.wait(None)?;
+        set_debug_name(
+            &logical_device.device,
+            image.inner().image,
+            "color grading lut",
+        );
+        Ok(ImageView::new(image)?)
+    }
+}
+
+/// Builds the pipeline of a custom effect for its pass's render pass.
+type BuildPipeline =
+    dyn Fn(&Arc<Device>, &Arc<RenderPass>) -> Result<Arc<EffectPipeline>> + Send + Sync;
+
+/// A fullscreen pass with a fragment shader of the caller's.
+///
+/// The shader reads `uv` at location 0, the source at set 0 binding 0 with
+/// its sampler at binding 1, and declares the push constants
+/// `{ vec4 params; vec2 texel_size; uint output_transfer; }`.
+#[derive(Clone)]
+pub struct CustomEffect {
+    /// Names the pipeline and the effect in the UI.
+    pub name: &'static str,
+    /// Passed to the shader as `params`.
+    pub params: [f32; 4],
+    build: Arc<BuildPipeline>,
+}
+
+impl CustomEffect {
+    /// `build` is usually `build_pipeline` with the entry point of a loaded
+    /// shader module.
+    pub fn new(
+        name: &'static str,
+        params: [f32; 4],
+        build: impl Fn(&Arc<Device>, &Arc<RenderPass>) -> Result<Arc<EffectPipeline>>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        Self {
+            name,
+            params,
+            build: Arc::new(build),
+        }
+    }
+}
+
+impl fmt::Debug for CustomEffect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CustomEffect")
+            .field("name", &self.name)
+            .field("params", &self.params)
+            .finish()
+    }
+}
+
+impl PartialEq for CustomEffect {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.params == other.params
+            && Arc::ptr_eq(&self.build, &other.build)
+    }
+}
+
+/// One step of the chain, applied in the order given to `PostChain::new`.
+/// Effects after `Tonemap` work on colors in [0, 1].
+#[derive(Clone, Debug, PartialEq)]
+pub enum PostEffect {
+    /// ACES filmic curve applied after scaling by `exposure`.
+    Tonemap {
+        exposure: f32,
+    },
+    /// Raises colors to `1 / gamma`. Only for `OutputTransfer::Linear`
+    /// surfaces with a UNORM format, the final blit already encodes sRGB and
+    /// HDR10 output and sRGB formats encode on write, so gamma would be
+    /// applied twice.
+    Gamma {
+        gamma: f32,
+    },
+    /// Darkens by up to `intensity` starting `radius` away from the center,
+    /// in UV units.
+    Vignette {
+        intensity: f32,
+        radius: f32,
+    },
+    /// Edge anti-aliasing searching up to `span` texels, best placed after
+    /// tonemapping.
+    Fxaa {
+        span: f32,
+    },
+    /// Blends between the input and its lookup in `lut` by `strength`.
+    ColorGrading {
+        lut: Lut,
+        strength: f32,
+    },
+    Custom(CustomEffect),
+}
+
+impl PostEffect {
+    fn get_pipeline(
+        &self,
+        device: &Arc<Device>,
+        renderpass: &Arc<RenderPass>,
+    ) -> Result<Arc<EffectPipeline>> {
+        let shader = match self {
+            Self::Tonemap { .. } => pipeline::Shader::Tonemap,
+            Self::Gamma { .. } => pipeline::Shader::Gamma,
+            Self::Vignette { .. } => pipeline::Shader::Vignette,
+            Self::Fxaa { .. } => pipeline::Shader::Fxaa,
+            Self::ColorGrading { .. } => pipeline::Shader::Grading,
+            Self::Custom(custom) => return (custom.build)(device, renderpass),
+        };
+        pipeline::get_pipeline(device, renderpass, shader)
+    }
+
+    /// Whether `other` only differs in values passed as push constants, so
+    /// it can replace this effect without new pipelines or images.
+    fn has_same_resources(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::ColorGrading { lut, .. }, Self::ColorGrading { lut: other, .. }) => lut == other,
+            (Self::Custom(custom), Self::Custom(other)) => {
+                custom.name == other.name && Arc::ptr_eq(&custom.build, &other.build)
+            }
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+
+    /// Matches `params` in the effect's shader.
+    fn get_params(&self) -> [f32; 4] {
+        match self {
+            Self::Tonemap { exposure } => [*exposure, 0.0, 0.0, 0.0],
+            Self::Gamma { gamma } => [*gamma, 0.0, 0.0, 0.0],
+            Self::Vignette { intensity, radius } => [*intensity, *radius, 0.0, 0.0],
+            Self::Fxaa { span } => [*span, 0.0, 0.0, 0.0],
+            Self::ColorGrading { lut, strength } => [*strength, lut.size as f32, 0.0, 0.0],
+            Self::Custom(custom) => custom.params,
+        }
+    }
+}
+
+/// Points the scene pass at an HDR target and appends a pass per effect and
+/// the blit to the swapchain image.
+pub fn add_passes(graph: &mut RenderGraph, effect_count: usize) -> Result<()> {
+    if effect_count > MAX_EFFECTS {
+        return Err(Error::RenderGraph(format!(
+            "at most {} post effects",
+            MAX_EFFECTS
+        )));
+    }
+    if let Some(info) = graph.get_attachment(SCENE_COLOR).copied() {
+        graph.add_attachment(
+            SCENE_COLOR,
+            AttachmentInfo {
+                format: HDR_FORMAT,
+                ..info
+            },
+        );
+    }
+    let scene = graph
+        .get_pass_mut(SCENE_PASS)
+        .ok_or_else(|| Error::RenderGraph(format!("no {} pass", SCENE_PASS)))?;
+    for id in scene.color.iter_mut().chain(scene.resolve.iter_mut()) {
+        if *id == BACKBUFFER {
+            *id = HDR_COLOR;
+        }
+    }
+    let target = AttachmentInfo {
+        format: HDR_FORMAT,
+        samples: 1,
+        size: AttachmentSize::Swapchain,
+        clear: [0.0, 0.0, 0.0, 1.0].into(),
+    };
+    graph.add_attachment(HDR_COLOR, target);
+    let mut source = HDR_COLOR;
+    for &name in &EFFECT_PASSES[..effect_count] {
+        graph.add_attachment(name, target);
+        graph.add_pass(PassDesc {
+            color: vec![name],
+            reads: vec![source],
+            ..PassDesc::new(name)
+        });
+        source = name;
+    }
+    graph.add_pass(PassDesc {
+        color: vec![BACKBUFFER],
+        reads: vec![source],
+        ..PassDesc::new(BLIT_PASS)
+    });
+    Ok(())
+}
+
+/// Descriptor set of a pass and the source it was built for.
+struct CachedSet {
+    source: AttachmentView,
+    set: Arc<dyn DescriptorSet + Send + Sync>,
+}
+
+struct Effect {
+    pass: &'static str,
+    /// None for the blit.
+    effect: Option<PostEffect>,
+    pipeline: Arc<EffectPipeline>,
+    /// Target of the previous pass.
+    source: ResourceId,
+    lut: Option<LutView>,
+    /// One per frame in flight, rebuilt when the targets are recreated.
+    sets: Vec<Option<CachedSet>>,
+}
+
+impl Effect {
+    fn get_set(
+        &mut self,
+        pass: &PassContext,
+        sampler: &Arc<Sampler>,
+    ) -> Result<Arc<dyn DescriptorSet + Send + Sync>> {
+        let source = pass
+            .get_attachment(self.source)
+            .ok_or_else(|| Error::RenderGraph(format!("{} was not created", self.source)))?;
+        if self.sets.len() <= pass.frame {
+            self.sets.resize_with(pass.frame + 1, || None);
+        }
+        if let Some(cached) = &self.sets[pass.frame] {
+            if Arc::ptr_eq(&cached.source, source) {
+                return Ok(cached.set.clone());
+            }
+        }
+        let layout = self
+            .pipeline
+            .descriptor_set_layout(0)
+            .expect("post shaders use set 0")
+            .clone();
+        let set = PersistentDescriptorSet::start(layout)
+            .add_image(source.clone())?
+            .add_sampler(sampler.clone())?;
+        let set: Arc<dyn DescriptorSet + Send + Sync> = match &self.lut {
+            Some(lut) => Arc::new(set.add_image(lut.clone())?.build()?),
+            None => Arc::new(set.build()?),
+        };
+        self.sets[pass.frame] = Some(CachedSet {
+            source: source.clone(),
+            set: set.clone(),
+        });
+        Ok(set)
+    }
+}
+
+/// Pipelines of the passes added by `add_passes`.
+pub struct PostChain {
+    effects: Vec<Effect>,
+    sampler: Arc<Sampler>,
+    output_transfer: OutputTransfer,
+}
+
+impl PostChain {
+    /// `render` must use a graph `add_passes` was called on with as many
+    /// effects.
+    pub fn new(
+        logical_device: &LogicalDevice,
+        render: &Render,
+        effects: &[PostEffect],
+    ) -> Result<Self> {
+        let device = &logical_device.device;
+        let get_render_pass = |name: &str| {
+            render
+                .get_render_pass(name)
+                .ok_or_else(|| Error::RenderGraph(format!("no live {} pass", name)))
+        };
+        let mut source = HDR_COLOR;
+        let mut chain = vec![];
+        for (effect, &pass) in effects.iter().zip(EFFECT_PASSES.iter()) {
+            let lut = match effect {
+                PostEffect::ColorGrading { lut, .. } => Some(lut.upload(logical_device)?),
+                _ => None,
+            };
+            chain.push(Effect {
+                pass,
+                effect: Some(effect.clone()),
+                pipeline: effect.get_pipeline(device, get_render_pass(pass)?)?,
+                source,
+                lut,
+                sets: vec![],
+            });
+            source = pass;
+        }
+        chain.push(Effect {
+            pass: BLIT_PASS,
+            effect: None,
+            pipeline: pipeline::get_pipeline(
+                device,
+                get_render_pass(BLIT_PASS)?,
+                pipeline::Shader::Blit,
+            )?,
+            source,
+            lut: None,
+            sets: vec![],
+        });
+        let sampler = Sampler::new(
+            device.clone(),
+            Filter::Linear,
+            Filter::Linear,
+            MipmapMode::Nearest,
+            SamplerAddressMode::ClampToEdge,
+            SamplerAddressMode::ClampToEdge,
+            SamplerAddressMode::ClampToEdge,
+            0.0,
+            1.0,
+            0.0,
+            0.0,
+        )?;
+        Ok(Self {
+            effects: chain,
+            sampler,
+            output_transfer: render.get_surface_format().get_transfer(),
+        })
+    }
+
+    /// Effects in chain order, as given to `new` or replaced since.
+    pub fn get_effects(&self) -> impl Iterator<Item = &PostEffect> {
+        self.effects
+            .iter()
+            .filter_map(|effect| effect.effect.as_ref())
+    }
+
+    /// Replaces the effect at `index` from the next frame on. Only values
+    /// passed as push constants may change; the variant, the LUT and the
+    /// custom shader must stay the same.
+    pub fn set_effect(&mut self, index: usize, effect: PostEffect) -> Result<()> {
+        let current = self
+            .effects
+            .get_mut(index)
+            .and_then(|current| current.effect.as_mut())
+            .ok_or(Error::PostEffect("no effect at this index"))?;
+        if !current.has_same_resources(&effect) {
+            return Err(Error::PostEffect("new pipelines or images would be needed"));
+        }
+        *current = effect;
+        Ok(())
+    }
+
+    /// Records `pass` if it belongs to the chain, returns false otherwise.
+    pub fn record(&mut self, pass: &mut PassContext) -> Result<bool> {
+        let sampler = &self.sampler;
+        let effect = match self
+            .effects
+            .iter_mut()
+            .find(|effect| effect.pass == pass.name)
+        {
+            Some(effect) => effect,
+            None => return Ok(false),
+        };
+        let set = effect.get_set(pass, sampler)?;
+        let params = match &effect.effect {
+            Some(effect) => effect.get_params(),
+            None => [0.0; 4],
+        };
+        let texel_size = match &pass.dynamic_state.viewports {
+            Some(viewports) => {
+                let [width, height] = viewports[0].dimensions;
+                [1.0 / width, 1.0 / height]
+            }
+            None => [0.0; 2],
+        };
+        let output_transfer = if effect.pass == BLIT_PASS {
+            self.output_transfer
+        } else {
+            OutputTransfer::Linear
+        };
+        let push_data = crate::shaders::blit_fs::ty::Params {
+            params,
+            texel_size,
+            output_transfer: output_transfer as u32,
+        };
+        pass.cmd_builder.draw(
+            effect.pipeline.clone(),
+            &pass.dynamic_state,
+            BufferlessVertices {
+                vertices: 3,
+                instances: 1,
+            },
+            set,
+            push_data,
+            vec![],
+        )?;
+        Ok(true)
+    }
+}
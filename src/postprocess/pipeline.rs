@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use vulkano::descriptor::PipelineLayoutAbstract;
+use vulkano::device::Device;
+use vulkano::pipeline::shader::GraphicsEntryPointAbstract;
+use vulkano::pipeline::{vertex::BufferlessDefinition, GraphicsPipeline};
+use vulkano::render_pass::RenderPass;
+
+use crate::device::set_debug_name;
+use crate::error::Result;
+use crate::render::graph::get_subpass;
+use crate::shaders::{
+    blit_fs, fullscreen_vs, fxaa_fs, gamma_fs, grading_fs, tonemap_fs, vignette_fs,
+};
+
+pub type EffectPipeline =
+    GraphicsPipeline<BufferlessDefinition, Box<dyn PipelineLayoutAbstract + Send + Sync + 'static>>;
+
+/// Fragment shader of a fullscreen pass, all share the vertex shader, the
+/// source bindings and the push constants.
+#[derive(Clone, Copy, Debug)]
+pub enum Shader {
+    Blit,
+    Tonemap,
+    Gamma,
+    Vignette,
+    Fxaa,
+    Grading,
+}
+
+impl Shader {
+    fn get_name(self) -> &'static str {
+        match self {
+            Self::Blit => "blit",
+            Self::Tonemap => "tonemap",
+            Self::Gamma => "gamma",
+            Self::Vignette => "vignette",
+            Self::Fxaa => "fxaa",
+            Self::Grading => "color grading",
+        }
+    }
+}
+
+/// Fullscreen pass running `fs`, which must take the `uv` input and declare
+/// the push constants of the built-in effects.
+pub fn build_pipeline<Fs>(
+    device: &Arc<Device>,
+    renderpass: &Arc<RenderPass>,
+    fs: Fs,
+    name: &str,
+) -> Result<Arc<EffectPipeline>>
+where
+    Fs: GraphicsEntryPointAbstract<SpecializationConstants = ()>,
+    Fs::PipelineLayout: Clone + Send + Sync + 'static,
+{
+    let vs = fullscreen_vs::Shader::load(device.clone())?;
+    let pipeline = Arc::new(
+        GraphicsPipeline::start()
+            .vertex_input(BufferlessDefinition)
+            .vertex_shader(vs.main_entry_point(), ())
+            .triangle_list()
+            .viewports_dynamic_scissors_irrelevant(1)
+            .fragment_shader(fs, ())
+            .render_pass(get_subpass(renderpass)?)
+            .build(device.clone())?,
+    );
+    set_debug_name(device, &*pipeline, &format!("{} pipeline", name));
+    Ok(pipeline)
+}
+
+pub fn get_pipeline(
+    device: &Arc<Device>,
+    renderpass: &Arc<RenderPass>,
+    shader: Shader,
+) -> Result<Arc<EffectPipeline>> {
+    // NOTE: every shader module has its own entry point type
+    macro_rules! build {
+        ($fs:ident) => {{
+            let fs = $fs::Shader::load(device.clone())?;
+            build_pipeline(device, renderpass, fs.main_entry_point(), shader.get_name())
+        }};
+    }
+    match shader {
+        Shader::Blit => build!(blit_fs),
+        Shader::Tonemap => build!(tonemap_fs),
+        Shader::Gamma => build!(gamma_fs),
+        Shader::Vignette => build!(vignette_fs),
+        Shader::Fxaa => build!(fxaa_fs),
+        Shader::Grading => build!(grading_fs),
+    }
+}
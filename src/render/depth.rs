@@ -0,0 +1,46 @@
+use vulkano::format::Format;
+use vulkano::instance::PhysicalDevice;
+
+use crate::error::{Error, Result};
+
+/// Depth formats in order of preference.
+const DEPTH_FORMATS: [Format; 3] = [Format::D32Sfloat, Format::D24Unorm_S8Uint, Format::D16Unorm];
+
+/// Depth formats with a stencil aspect in order of preference.
+const DEPTH_STENCIL_FORMATS: [Format; 3] = [
+    Format::D32Sfloat_S8Uint,
+    Format::D24Unorm_S8Uint,
+    Format::D16Unorm_S8Uint,
+];
+
+/// Depth formats shadow maps can be drawn to and sampled from.
+const SHADOW_FORMATS: [Format; 2] = [Format::D32Sfloat, Format::D16Unorm];
+
+pub fn get_shadow_format(physical: PhysicalDevice) -> Result<Format> {
+    SHADOW_FORMATS
+        .iter()
+        .copied()
+        .find(|format| {
+            let features = format.properties(physical).optimal_tiling_features;
+            features.depth_stencil_attachment && features.sampled_image
+        })
+        .ok_or(Error::Unsupported("shadow map format"))
+}
+
+pub fn get_depth_format(physical: PhysicalDevice, stencil: bool) -> Result<Format> {
+    let formats = if stencil {
+        &DEPTH_STENCIL_FORMATS
+    } else {
+        &DEPTH_FORMATS
+    };
+    formats
+        .iter()
+        .copied()
+        .find(|format| {
+            format
+                .properties(physical)
+                .optimal_tiling_features
+                .depth_stencil_attachment
+        })
+        .ok_or(Error::Unsupported("depth format"))
+}
@@ -0,0 +1,652 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+
+use vulkano::command_buffer::{
+    AutoCommandBufferBuilder, DynamicState, PrimaryAutoCommandBuffer, SubpassContents,
+};
+use vulkano::device::Device;
+use vulkano::format::{ClearValue, Format, FormatTy};
+use vulkano::image::{
+    view::{ImageView, ImageViewAbstract},
+    AttachmentImage, ImageAccess, ImageLayout, ImageUsage, SwapchainImage,
+};
+use vulkano::pipeline::viewport::Viewport;
+use vulkano::render_pass::{
+    AttachmentDesc, Framebuffer, FramebufferAbstract, LoadOp, RenderPass, RenderPassDesc, StoreOp,
+    Subpass, SubpassDesc,
+};
+
+use winit::window::Window;
+
+use crate::device::set_debug_name;
+use crate::error::{Error, Result};
+
+/// Name of an attachment or buffer in the graph.
+pub type ResourceId = &'static str;
+
+/// The swapchain image being rendered, the default output of the graph.
+pub const BACKBUFFER: ResourceId = "backbuffer";
+
+pub type AttachmentView = Arc<ImageView<Arc<AttachmentImage>>>;
+type AnyFramebuffer = Arc<dyn FramebufferAbstract + Send + Sync>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AttachmentSize {
+    /// Same size as the swapchain images.
+    Swapchain,
+    /// Swapchain size multiplied by a factor.
+    Scaled(f32),
+    /// Fixed size, e.g. for shadow maps.
+    Absolute([u32; 2]),
+}
+
+impl AttachmentSize {
+    fn resolve(self, swapchain: [u32; 2]) -> [u32; 2] {
+        match self {
+            Self::Swapchain => swapchain,
+            Self::Scaled(factor) => [
+                ((swapchain[0] as f32 * factor) as u32).max(1),
+                ((swapchain[1] as f32 * factor) as u32).max(1),
+            ],
+            Self::Absolute(size) => size,
+        }
+    }
+}
+
+/// An image created and owned by the graph.
+#[derive(Clone, Copy, Debug)]
+pub struct AttachmentInfo {
+    pub format: Format,
+    pub samples: u32,
+    pub size: AttachmentSize,
+    /// Value the first pass writing the attachment clears it to.
+    pub clear: ClearValue,
+}
+
+/// A pass and the resources it uses, the graph only looks at these to order
+/// and cull passes; the commands are recorded by the caller of `execute`.
+#[derive(Clone, Debug, Default)]
+pub struct PassDesc {
+    pub name: &'static str,
+    /// Color attachments in location order.
+    pub color: Vec<ResourceId>,
+    pub depth: Option<ResourceId>,
+    /// Single sampled attachments the color attachments are resolved into,
+    /// empty or one per color attachment.
+    pub resolve: Vec<ResourceId>,
+    /// Attachments sampled or buffers read by the pass.
+    pub reads: Vec<ResourceId>,
+    /// Buffers or other resources written outside of attachments.
+    pub writes: Vec<ResourceId>,
+}
+
+impl PassDesc {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            ..Self::default()
+        }
+    }
+
+    /// Attachments in render pass order: color, depth, resolve.
+    fn attachments(&self) -> Vec<ResourceId> {
+        self.color
+            .iter()
+            .chain(self.depth.iter())
+            .chain(self.resolve.iter())
+            .copied()
+            .collect()
+    }
+
+    fn outputs(&self) -> Vec<ResourceId> {
+        let mut outputs = self.attachments();
+        outputs.extend(self.writes.iter().copied());
+        outputs
+    }
+}
+
+/// Passes and attachments of a frame, compiled into render passes once and
+/// into images per frame in flight.
+#[derive(Clone, Debug)]
+pub struct RenderGraph {
+    attachments: HashMap<ResourceId, AttachmentInfo>,
+    passes: Vec<PassDesc>,
+    outputs: Vec<ResourceId>,
+}
+
+impl Default for RenderGraph {
+    fn default() -> Self {
+        Self {
+            attachments: HashMap::new(),
+            passes: vec![],
+            outputs: vec![BACKBUFFER],
+        }
+    }
+}
+
+impl RenderGraph {
+    pub fn add_attachment(&mut self, id: ResourceId, info: AttachmentInfo) -> &mut Self {
+        self.attachments.insert(id, info);
+        self
+    }
+
+    /// Passes writing the same resource run in the order they were added,
+    /// any other order comes from what they read.
+    pub fn add_pass(&mut self, pass: PassDesc) -> &mut Self {
+        self.passes.retain(|other| other.name != pass.name);
+        self.passes.push(pass);
+        self
+    }
+
+    pub fn remove_pass(&mut self, name: &str) -> &mut Self {
+        self.passes.retain(|pass| pass.name != name);
+        self
+    }
+
+    /// Keeps passes writing `id` alive even if nothing reads it.
+    pub fn add_output(&mut self, id: ResourceId) -> &mut Self {
+        self.outputs.push(id);
+        self
+    }
+
+    pub fn get_attachment(&self, id: ResourceId) -> Option<&AttachmentInfo> {
+        self.attachments.get(id)
+    }
+
+    /// For redirecting an existing pass, e.g. the scene into an offscreen
+    /// target.
+    pub fn get_pass_mut(&mut self, name: &str) -> Option<&mut PassDesc> {
+        self.passes.iter_mut().find(|pass| pass.name == name)
+    }
+
+    /// Indices of the passes each pass must run after.
+    fn get_dependencies(&self) -> Vec<BTreeSet<usize>> {
+        let mut writers: HashMap<ResourceId, Vec<usize>> = HashMap::new();
+        for (index, pass) in self.passes.iter().enumerate() {
+            for id in pass.outputs() {
+                writers.entry(id).or_default().push(index);
+            }
+        }
+        self.passes
+            .iter()
+            .enumerate()
+            .map(|(index, pass)| {
+                let mut dependencies = BTreeSet::new();
+                for id in &pass.reads {
+                    let others = writers.get(id).into_iter().flatten();
+                    dependencies.extend(others.filter(|&&other| other != index));
+                }
+                for id in pass.outputs() {
+                    // NOTE: earlier writers of the same resource run first
+                    let others = writers.get(id).into_iter().flatten();
+                    dependencies.extend(others.filter(|&&other| other < index));
+                }
+                dependencies
+            })
+            .collect()
+    }
+
+    /// Passes contributing to an output, in execution order.
+    fn schedule(&self) -> Result<Vec<usize>> {
+        let dependencies = self.get_dependencies();
+        let mut live: HashSet<usize> = HashSet::new();
+        let mut stack: Vec<usize> = self
+            .passes
+            .iter()
+            .enumerate()
+            .filter(|(_, pass)| pass.outputs().iter().any(|id| self.outputs.contains(id)))
+            .map(|(index, _)| index)
+            .collect();
+        while let Some(index) = stack.pop() {
+            if live.insert(index) {
+                stack.extend(dependencies[index].iter().copied());
+            }
+        }
+
+        let mut order = vec![];
+        let mut done: HashSet<usize> = HashSet::new();
+        while order.len() < live.len() {
+            let next = (0..self.passes.len()).find(|index| {
+                live.contains(index)
+                    && !done.contains(index)
+                    && dependencies[*index]
+                        .iter()
+                        .all(|other| done.contains(other))
+            });
+            match next {
+                Some(index) => {
+                    done.insert(index);
+                    order.push(index);
+                }
+                None => return Err(Error::RenderGraph("passes depend on each other".into())),
+            }
+        }
+        Ok(order)
+    }
+
+    /// Orders and culls the passes and creates a render pass for each pass
+    /// drawing to attachments.
+    pub fn compile(&self, device: &Arc<Device>, swapchain_format: Format) -> Result<CompiledGraph> {
+        let mut attachments = self.attachments.clone();
+        attachments.insert(
+            BACKBUFFER,
+            AttachmentInfo {
+                format: swapchain_format,
+                samples: 1,
+                size: AttachmentSize::Swapchain,
+                clear: [0.0, 0.0, 0.0, 1.0].into(),
+            },
+        );
+        let order = self.schedule()?;
+        let passes: Vec<&PassDesc> = order.iter().map(|&index| &self.passes[index]).collect();
+        for id in passes.iter().flat_map(|pass| pass.attachments()) {
+            if !attachments.contains_key(id) {
+                return Err(Error::RenderGraph(format!("unknown attachment {}", id)));
+            }
+        }
+
+        let mut sampled = HashSet::new();
+        let mut stored = HashSet::new();
+        let mut compiled = vec![];
+        for (position, pass) in passes.iter().enumerate() {
+            let later = &passes[position + 1..];
+            let is_used_later = |id: ResourceId| {
+                self.outputs.contains(&id)
+                    || later
+                        .iter()
+                        .any(|other| other.reads.contains(&id) || other.attachments().contains(&id))
+            };
+            let is_written_before = |id: ResourceId| {
+                passes[..position]
+                    .iter()
+                    .any(|other| other.attachments().contains(&id))
+            };
+            let ids = pass.attachments();
+            let mut descs = vec![];
+            let mut clear_values = vec![];
+            for id in &ids {
+                let info = &attachments[id];
+                let resolved = pass.resolve.contains(id);
+                let load = if is_written_before(id) {
+                    LoadOp::Load
+                } else if resolved {
+                    // NOTE: the resolve attachment is fully overwritten
+                    LoadOp::DontCare
+                } else {
+                    LoadOp::Clear
+                };
+                clear_values.push(match load {
+                    LoadOp::Clear => info.clear,
+                    _ => ClearValue::None,
+                });
+                let store = if is_used_later(id) {
+                    stored.insert(*id);
+                    StoreOp::Store
+                } else {
+                    StoreOp::DontCare
+                };
+                let layout = if resolved {
+                    ImageLayout::TransferDstOptimal
+                } else if pass.depth == Some(*id) {
+                    ImageLayout::DepthStencilAttachmentOptimal
+                } else {
+                    ImageLayout::ColorAttachmentOptimal
+                };
+                descs.push(AttachmentDesc {
+                    format: info.format,
+                    samples: info.samples,
+                    load,
+                    store,
+                    stencil_load: load,
+                    stencil_store: store,
+                    initial_layout: layout,
+                    final_layout: layout,
+                });
+            }
+            sampled.extend(pass.reads.iter().copied());
+
+            let render_pass = if ids.is_empty() {
+                None
+            } else {
+                let index_of = |id: &ResourceId| ids.iter().position(|other| other == id).unwrap();
+                let subpass = SubpassDesc {
+                    color_attachments: pass
+                        .color
+                        .iter()
+                        .map(|id| (index_of(id), ImageLayout::ColorAttachmentOptimal))
+                        .collect(),
+                    depth_stencil: pass
+                        .depth
+                        .as_ref()
+                        .map(|id| (index_of(id), ImageLayout::DepthStencilAttachmentOptimal)),
+                    input_attachments: vec![],
+                    resolve_attachments: pass
+                        .resolve
+                        .iter()
+                        .map(|id| (index_of(id), ImageLayout::TransferDstOptimal))
+                        .collect(),
+                    preserve_attachments: vec![],
+                };
+                let desc = RenderPassDesc::new(descs, vec![subpass], vec![]);
+                let render_pass = Arc::new(RenderPass::new(device.clone(), desc)?);
+                set_debug_name(
+                    device,
+                    &render_pass.inner(),
+                    &format!("{} render pass", pass.name),
+                );
+                Some(render_pass)
+            };
+            compiled.push(CompiledPass {
+                name: pass.name,
+                attachments: ids,
+                render_pass,
+                clear_values,
+            });
+        }
+
+        let culled: Vec<_> = self
+            .passes
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !order.contains(index))
+            .map(|(_, pass)| pass.name)
+            .collect();
+        if !culled.is_empty() {
+            log::debug!("culled passes: {:?}", culled);
+        }
+        Ok(CompiledGraph {
+            passes: compiled,
+            culled,
+            attachments,
+            sampled,
+            stored,
+        })
+    }
+}
+
+struct CompiledPass {
+    name: &'static str,
+    attachments: Vec<ResourceId>,
+    render_pass: Option<Arc<RenderPass>>,
+    clear_values: Vec<ClearValue>,
+}
+
+/// Live passes in execution order with their render passes.
+pub struct CompiledGraph {
+    passes: Vec<CompiledPass>,
+    culled: Vec<&'static str>,
+    attachments: HashMap<ResourceId, AttachmentInfo>,
+    /// Attachments read by a later pass, created with sampled usage.
+    sampled: HashSet<ResourceId>,
+    /// Attachments whose content outlives a pass, never transient.
+    stored: HashSet<ResourceId>,
+}
+
+/// Images and framebuffers of one frame in flight.
+pub struct GraphTargets {
+    images: HashMap<ResourceId, AttachmentView>,
+    /// Per pass, one framebuffer per swapchain image.
+    framebuffers: Vec<Vec<AnyFramebuffer>>,
+}
+
+/// A pass being recorded, handed to the callback of `CompiledGraph::execute`
+/// inside the pass's render pass.
+pub struct PassContext<'a> {
+    pub name: &'static str,
+    /// Frame in flight being recorded, each has its own attachments.
+    pub frame: usize,
+    pub cmd_builder: &'a mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    /// Viewport covering the pass's attachments.
+    pub dynamic_state: DynamicState,
+    targets: &'a GraphTargets,
+}
+
+impl PassContext<'_> {
+    /// Attachment written by an earlier pass, for sampling.
+    pub fn get_attachment(&self, id: ResourceId) -> Option<&AttachmentView> {
+        self.targets.images.get(id)
+    }
+}
+
+/// Framebuffers need their attachment count in the type, so build them from
+/// a slice for the counts render passes use.
+fn build_framebuffer(
+    render_pass: &Arc<RenderPass>,
+    views: &[Arc<dyn ImageViewAbstract + Send + Sync>],
+) -> Result<AnyFramebuffer> {
+    let start = Framebuffer::start(render_pass.clone());
+    Ok(match views {
+        [a] => Arc::new(start.add(a.clone())?.build()?),
+        [a, b] => Arc::new(start.add(a.clone())?.add(b.clone())?.build()?),
+        [a, b, c] => Arc::new(
+            start
+                .add(a.clone())?
+                .add(b.clone())?
+                .add(c.clone())?
+                .build()?,
+        ),
+        [a, b, c, d] => Arc::new(
+            start
+                .add(a.clone())?
+                .add(b.clone())?
+                .add(c.clone())?
+                .add(d.clone())?
+                .build()?,
+        ),
+        _ => {
+            return Err(Error::RenderGraph(format!(
+                "{} attachments in one pass",
+                views.len()
+            )))
+        }
+    })
+}
+
+/// The only subpass of a pass's render pass, for building its pipelines.
+pub fn get_subpass(render_pass: &Arc<RenderPass>) -> Result<Subpass> {
+    Subpass::from(render_pass.clone(), 0)
+        .ok_or_else(|| Error::RenderGraph("render pass has no subpass".into()))
+}
+
+impl CompiledGraph {
+    pub fn get_render_pass(&self, name: &str) -> Option<&Arc<RenderPass>> {
+        self.passes
+            .iter()
+            .find(|pass| pass.name == name)
+            .and_then(|pass| pass.render_pass.as_ref())
+    }
+
+    /// Passes that do not contribute to any output.
+    pub fn get_culled(&self) -> &[&'static str] {
+        &self.culled
+    }
+
+    /// Creates the attachments and framebuffers for one frame in flight.
+    pub fn create_targets(
+        &self,
+        device: &Arc<Device>,
+        dimensions: [u32; 2],
+        swapchain_images: &[Arc<SwapchainImage<Window>>],
+    ) -> Result<GraphTargets> {
+        let mut images = HashMap::new();
+        for id in self.passes.iter().flat_map(|pass| pass.attachments.iter()) {
+            if *id == BACKBUFFER || images.contains_key(id) {
+                continue;
+            }
+            let info = &self.attachments[id];
+            let is_depth = matches!(
+                info.format.ty(),
+                FormatTy::Depth | FormatTy::DepthStencil | FormatTy::Stencil
+            );
+            let sampled = self.sampled.contains(id);
+            let usage = ImageUsage {
+                color_attachment: !is_depth,
+                depth_stencil_attachment: is_depth,
+                sampled,
+                transient_attachment: !sampled && !self.stored.contains(id),
+                ..ImageUsage::none()
+            };
+            let image = AttachmentImage::multisampled_with_usage(
+                device.clone(),
+                info.size.resolve(dimensions),
+                info.samples,
+                info.format,
+                usage,
+            )?;
+            set_debug_name(device, image.inner().image, &format!("{} attachment", id));
+            images.insert(*id, ImageView::new(image)?);
+        }
+
+        let backbuffers = swapchain_images
+            .iter()
+            .enumerate()
+            .map(|(index, image)| {
+                set_debug_name(
+                    device,
+                    image.inner().image,
+                    &format!("swapchain image {}", index),
+                );
+                Ok(ImageView::new(image.clone())?)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut framebuffers = vec![];
+        for pass in &self.passes {
+            let render_pass = match &pass.render_pass {
+                Some(render_pass) => render_pass,
+                None => {
+                    framebuffers.push(vec![]);
+                    continue;
+                }
+            };
+            let per_image = backbuffers
+                .iter()
+                .enumerate()
+                .map(|(index, backbuffer)| {
+                    let views: Vec<Arc<dyn ImageViewAbstract + Send + Sync>> = pass
+                        .attachments
+                        .iter()
+                        .map(|id| match *id {
+                            BACKBUFFER => backbuffer.clone() as Arc<_>,
+                            id => images[id].clone() as Arc<_>,
+                        })
+                        .collect();
+                    let framebuffer = build_framebuffer(render_pass, &views)?;
+                    set_debug_name(
+                        device,
+                        &framebuffer.inner(),
+                        &format!("{} framebuffer {}", pass.name, index),
+                    );
+                    Ok(framebuffer)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            framebuffers.push(per_image);
+        }
+        Ok(GraphTargets {
+            images,
+            framebuffers,
+        })
+    }
+
+    /// Runs the live passes in order, `record` is called once per pass inside
+    /// its render pass. Layout transitions between passes are inserted by the
+    /// command buffer builder. `targets` belong to frame in flight `frame`.
+    pub fn execute(
+        &self,
+        targets: &GraphTargets,
+        frame: usize,
+        image_index: usize,
+        cmd_builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        mut record: impl FnMut(&mut PassContext) -> Result<()>,
+    ) -> Result<()> {
+        for (pass, framebuffers) in self.passes.iter().zip(&targets.framebuffers) {
+            let framebuffer = framebuffers.get(image_index);
+            let mut dynamic_state = DynamicState::none();
+            if let Some(framebuffer) = framebuffer {
+                let [width, height, _] = framebuffer.dimensions();
+                dynamic_state.viewports = Some(vec![Viewport {
+                    origin: [0.0, 0.0],
+                    dimensions: [width as f32, height as f32],
+                    depth_range: 0.0..1.0,
+                }]);
+                cmd_builder.begin_render_pass(
+                    framebuffer.clone(),
+                    SubpassContents::Inline,
+                    pass.clear_values.clone(),
+                )?;
+            }
+            record(&mut PassContext {
+                name: pass.name,
+                frame,
+                cmd_builder,
+                dynamic_state,
+                targets,
+            })?;
+            if framebuffer.is_some() {
+                cmd_builder.end_render_pass()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pass(name: &'static str, reads: &[ResourceId], writes: &[ResourceId]) -> PassDesc {
+        PassDesc {
+            reads: reads.to_vec(),
+            writes: writes.to_vec(),
+            ..PassDesc::new(name)
+        }
+    }
+
+    fn get_order(graph: &RenderGraph) -> Result<Vec<&'static str>> {
+        let order = graph.schedule()?;
+        Ok(order
+            .iter()
+            .map(|&index| graph.passes[index].name)
+            .collect())
+    }
+
+    #[test]
+    fn reader_runs_after_writer() {
+        let mut graph = RenderGraph::default();
+        graph
+            .add_pass(pass("post", &["scene"], &[BACKBUFFER]))
+            .add_pass(pass("scene", &[], &["scene"]));
+        assert_eq!(get_order(&graph).unwrap(), ["scene", "post"]);
+    }
+
+    #[test]
+    fn writers_of_one_resource_keep_their_order() {
+        let mut graph = RenderGraph::default();
+        graph
+            .add_pass(pass("scene", &[], &[BACKBUFFER]))
+            .add_pass(pass("overlay", &[], &[BACKBUFFER]))
+            .add_pass(pass("debug", &[], &[BACKBUFFER]));
+        assert_eq!(get_order(&graph).unwrap(), ["scene", "overlay", "debug"]);
+    }
+
+    #[test]
+    fn passes_without_used_output_are_culled() {
+        let mut graph = RenderGraph::default();
+        graph
+            .add_pass(pass("unused", &[], &["unused"]))
+            .add_pass(pass("shadow", &[], &["shadow"]))
+            .add_pass(pass("scene", &["shadow"], &[BACKBUFFER]));
+        assert_eq!(get_order(&graph).unwrap(), ["shadow", "scene"]);
+
+        graph.add_output("unused");
+        assert_eq!(get_order(&graph).unwrap(), ["unused", "shadow", "scene"]);
+    }
+
+    #[test]
+    fn cycle_is_an_error() {
+        let mut graph = RenderGraph::default();
+        graph
+            .add_pass(pass("a", &["b"], &["a", BACKBUFFER]))
+            .add_pass(pass("b", &["a"], &["b"]));
+        assert!(matches!(get_order(&graph), Err(Error::RenderGraph(_))));
+    }
+}
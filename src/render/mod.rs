@@ -1,64 +1,158 @@
 use std::sync::Arc;
 
 use vulkano::{
-    command_buffer::{
-        AutoCommandBufferBuilder, DynamicState, PrimaryAutoCommandBuffer, SubpassContents,
-    },
+    command_buffer::{AutoCommandBufferBuilder, PrimaryAutoCommandBuffer, PrimaryCommandBuffer},
     device::{Device, Queue},
     image::SwapchainImage,
-    render_pass::{FramebufferAbstract, RenderPass},
-    swapchain::{self, AcquireError, Surface, Swapchain, SwapchainAcquireFuture},
-    sync::{self, FlushError, GpuFuture},
+    render_pass::RenderPass,
+    swapchain::{
+        self, AcquireError, Surface, Swapchain, SwapchainAcquireFuture, SwapchainCreationError,
+    },
+    sync::{self, FenceSignalFuture, FlushError, GpuFuture},
 };
 
 use winit::window::Window;
 
-use crate::device::LogicalDevice;
+use crate::device::{set_debug_name, LogicalDevice};
+use crate::error::{Error, Result};
 
-mod dynamicstate;
-mod framebuffers;
+mod depth;
+pub mod graph;
+mod multisample;
 mod renderpass;
 mod swapchains;
 
+pub use depth::get_shadow_format;
+pub use graph::{CompiledGraph, GraphTargets, PassContext, RenderGraph};
+pub use renderpass::{add_overlay_pass, DEPTH, OVERLAY_PASS, SCENE_COLOR, SCENE_PASS};
+pub use swapchains::{HdrMode, OutputTransfer, PresentModeConfig, SurfaceFormat, SwapchainConfig};
+
+#[derive(Clone, Copy, Debug)]
+pub struct RenderConfig {
+    /// Requested MSAA sample count (1, 2, 4 or 8), lowered to what the
+    /// device supports.
+    pub samples: u32,
+    /// Pick a depth format with a stencil aspect.
+    pub stencil: bool,
+    /// Clear depth to 0 and keep fragments with greater depth, for use with
+    /// an infinite reversed projection (see `Camera::reverse_z`).
+    pub reverse_z: bool,
+    /// Number of frames the CPU may record ahead of the GPU. Each frame owns
+    /// its fence and the render graph's attachments.
+    pub frames_in_flight: usize,
+    pub swapchain: SwapchainConfig,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            samples: 1,
+            stencil: false,
+            reverse_z: false,
+            frames_in_flight: 2,
+            swapchain: SwapchainConfig::default(),
+        }
+    }
+}
+
+type FrameFuture = FenceSignalFuture<Box<dyn GpuFuture + Send + Sync>>;
+
 pub struct Render {
     device: Arc<Device>,
     swapchain: Arc<Swapchain<Window>>,
-    resizehelper: dynamicstate::ResizeHelper,
-    surface: Arc<Surface<Window>>,
     images: Vec<Arc<SwapchainImage<Window>>>,
-    pub renderpass: Arc<RenderPass>,
+    config: RenderConfig,
+    surface_format: SurfaceFormat,
     acquire_future: Option<SwapchainAcquireFuture<Window>>,
-    framebuffers: Vec<Arc<dyn FramebufferAbstract + Send + Sync>>,
+    graph: RenderGraph,
+    compiled_graph: CompiledGraph,
+    /// Attachments and framebuffers of the graph per frame in flight.
+    graph_targets: Vec<GraphTargets>,
     recreate_swapchain: bool,
     current_image_index: usize,
-    previous_frame_end: Option<Box<dyn GpuFuture>>,
+    current_frame: usize,
+    /// Fence of the last submission of each frame in flight.
+    frame_fences: Vec<Option<Arc<FrameFuture>>>,
+    /// Physical pixels per logical pixel of the window.
+    scale_factor: f64,
+    /// The window had no area to draw to when last checked.
+    suspended: bool,
 }
 
 impl Render {
     pub fn recreate_swapchain(&mut self) {
         self.recreate_swapchain = true
     }
-    pub fn new(logical_device: &LogicalDevice, surface: &Arc<Surface<Window>>) -> Self {
-        let previous_frame_end = Some(sync::now(logical_device.device.clone()).boxed());
-        let (swapchain, images) = swapchains::get_swapchain(&surface, &logical_device);
-        let resizehelper = dynamicstate::ResizeHelper::new(&swapchain);
-        let renderpass = renderpass::get_render_pass(&logical_device.device, &swapchain);
+    pub fn new(
+        logical_device: &LogicalDevice,
+        surface: &Arc<Surface<Window>>,
+        config: RenderConfig,
+    ) -> Result<Self> {
+        let ((swapchain, images), surface_format) =
+            swapchains::get_swapchain(surface, logical_device, &config.swapchain)?;
+        let physical = logical_device.device.physical_device();
+        let config = RenderConfig {
+            samples: multisample::get_sample_count(physical, config.samples),
+            frames_in_flight: config.frames_in_flight.max(1),
+            ..config
+        };
+        let depth_format = depth::get_depth_format(physical, config.stencil)?;
+        let graph = renderpass::get_default_graph(
+            swapchain.format(),
+            config.samples,
+            depth_format,
+            config.reverse_z,
+        );
         let device = logical_device.device.clone();
-        let framebuffers =
-            framebuffers::get_frame_buffer(&swapchain, &device, &images, &renderpass);
-        Self {
-            surface: surface.clone(),
+        let compiled_graph = graph.compile(&device, swapchain.format())?;
+        let graph_targets = (0..config.frames_in_flight)
+            .map(|_| compiled_graph.create_targets(&device, swapchain.dimensions(), &images))
+            .collect::<Result<_>>()?;
+        let frame_fences = vec![None; config.frames_in_flight];
+        let scale_factor = surface.window().scale_factor();
+        Ok(Self {
             device,
             swapchain,
             images,
-            renderpass,
-            framebuffers,
-            previous_frame_end,
-            resizehelper,
+            config,
+            surface_format,
+            graph,
+            compiled_graph,
+            graph_targets,
+            frame_fences,
+            scale_factor,
+            suspended: false,
             acquire_future: Default::default(),
             recreate_swapchain: Default::default(),
             current_image_index: Default::default(),
+            current_frame: Default::default(),
+        })
+    }
+
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        self.scale_factor = scale_factor;
+    }
+
+    /// Scale to apply to sizes given in logical pixels, such as UI and text.
+    pub fn get_scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Whether the window has no area to draw to, e.g. while minimized.
+    /// Leaving that state schedules a fresh swapchain.
+    pub fn check_suspended(&mut self) -> bool {
+        let [width, height]: [u32; 2] = self.swapchain.surface().window().inner_size().into();
+        let suspended = width == 0 || height == 0;
+        if self.suspended && !suspended {
+            self.recreate_swapchain = true;
         }
+        self.suspended = suspended;
+        suspended
+    }
+
+    /// Size of the swapchain images in physical pixels.
+    pub fn get_dimensions(&self) -> [u32; 2] {
+        self.swapchain.dimensions()
     }
 
     pub fn get_aspect_ratio(&self) -> f32 {
@@ -69,92 +163,166 @@ impl Render {
     pub fn get_command_buffer_builder(
         &mut self,
         graphical_queue: Arc<Queue>,
-    ) -> Option<AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>> {
-        self.previous_frame_end.as_mut().unwrap().cleanup_finished();
+    ) -> Result<Option<AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>>> {
+        // NOTE: wait until the GPU is done with this frame's resources
+        if let Some(fence) = self.frame_fences[self.current_frame].take() {
+            fence.wait(None)?;
+        }
         if self.recreate_swapchain {
-            self.recreate_swapchain = self.resizehelper.resize(
-                &self.device,
-                &self.surface,
-                &self.renderpass,
-                &mut self.swapchain,
-                &mut self.framebuffers,
-                &mut self.images,
-            );
+            if !self.resize()? {
+                // NOTE: zero sized surface, try again on the next frame
+                return Ok(None);
+            }
+            self.recreate_swapchain = false;
         }
         let (image_num, suboptimal, acquire_future) =
             match swapchain::acquire_next_image(self.swapchain.clone(), None) {
                 Ok(r) => r,
                 Err(AcquireError::OutOfDate) => {
                     self.recreate_swapchain = true;
-                    return None;
+                    return Ok(None);
                 }
-                Err(e) => panic!("failed due to {:?}", e),
+                Err(e) => return Err(e.into()),
             };
         self.acquire_future = Some(acquire_future);
         self.current_image_index = image_num;
         if suboptimal {
             self.recreate_swapchain = true;
         }
-        let frame = self.framebuffers[image_num].clone();
-        let mut cmd_builder = AutoCommandBufferBuilder::primary(
+        let cmd_builder = AutoCommandBufferBuilder::primary(
             self.device.clone(),
             graphical_queue.family(),
             vulkano::command_buffer::CommandBufferUsage::OneTimeSubmit,
+        )?;
+        Ok(Some(cmd_builder))
+    }
+
+    /// Rebuilds the swapchain and the graph's attachments, returns false if
+    /// the surface has no size.
+    fn resize(&mut self) -> Result<bool> {
+        let (swapchain, images) =
+            match swapchains::recreate_swapchain(&self.swapchain, &self.config.swapchain) {
+                Ok(r) => r,
+                Err(Error::Swapchain(SwapchainCreationError::UnsupportedDimensions)) => {
+                    return Ok(false)
+                }
+                Err(e) => return Err(e),
+            };
+        self.swapchain = swapchain;
+        self.images = images;
+        self.create_graph_targets()?;
+        Ok(true)
+    }
+
+    fn create_graph_targets(&mut self) -> Result<()> {
+        self.graph_targets = (0..self.config.frames_in_flight)
+            .map(|_| {
+                self.compiled_graph.create_targets(
+                    &self.device,
+                    self.swapchain.dimensions(),
+                    &self.images,
+                )
+            })
+            .collect::<Result<_>>()?;
+        Ok(())
+    }
+
+    /// The graph frames are recorded with, a copy can be extended with more
+    /// passes and handed to `set_render_graph`.
+    pub fn get_render_graph(&self) -> &RenderGraph {
+        &self.graph
+    }
+
+    /// Compiles `graph` and creates its attachments. Pipelines made for the
+    /// previous graph keep working as long as their pass keeps its
+    /// attachment formats and sample counts.
+    pub fn set_render_graph(&mut self, graph: RenderGraph) -> Result<()> {
+        self.compiled_graph = graph.compile(&self.device, self.swapchain.format())?;
+        self.graph = graph;
+        self.create_graph_targets()
+    }
+
+    /// Render pass of a live pass of the graph, for creating its pipelines.
+    pub fn get_render_pass(&self, pass: &str) -> Option<&Arc<RenderPass>> {
+        self.compiled_graph.get_render_pass(pass)
+    }
+
+    /// Records every live pass of the graph for the acquired image.
+    pub fn execute_graph(
+        &self,
+        cmd_builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        record: impl FnMut(&mut PassContext) -> Result<()>,
+    ) -> Result<()> {
+        self.compiled_graph.execute(
+            &self.graph_targets[self.current_frame],
+            self.current_frame,
+            self.current_image_index,
+            cmd_builder,
+            record,
         )
-        .unwrap();
-        cmd_builder
-            .begin_render_pass(
-                frame.clone(),
-                SubpassContents::Inline,
-                vec![[0.0, 0.0, 0.0, 1.0].into(), 1f32.into()],
-            )
-            .unwrap();
-        Some(cmd_builder)
     }
+
+    /// Changes present mode or image count; the swapchain is rebuilt before
+    /// the next frame.
+    pub fn set_swapchain_config(&mut self, config: SwapchainConfig) {
+        self.config.swapchain = config;
+        self.recreate_swapchain = true;
+    }
+
+    pub fn get_swapchain_config(&self) -> SwapchainConfig {
+        self.config.swapchain
+    }
+
+    /// Format of the swapchain images, shaders use its transfer to encode
+    /// their output.
+    pub fn get_surface_format(&self) -> SurfaceFormat {
+        self.surface_format
+    }
+
+    pub fn is_reverse_z(&self) -> bool {
+        self.config.reverse_z
+    }
+
     pub fn render(
         &mut self,
-        mut cmd_builder: AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        cmd_builder: AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
         graphical_queue: &Arc<Queue>,
         present_queue: &Arc<Queue>,
-    ) {
-        if cmd_builder.end_render_pass().is_err() {
-            eprintln!("render pass already ended");
-        }
-        let command_buffer = Arc::new(cmd_builder.build().unwrap());
-        let future = self
-            .previous_frame_end
-            .take()
-            .unwrap()
-            .join(
-                self.acquire_future
-                    .take()
-                    .expect("expected acquire_future to be present"),
-            )
-            .then_execute(graphical_queue.clone(), command_buffer)
-            .unwrap()
+    ) -> Result<()> {
+        let command_buffer = cmd_builder.build()?;
+        set_debug_name(
+            &self.device,
+            command_buffer.inner(),
+            &format!("frame {} commands", self.current_frame),
+        );
+        let command_buffer = Arc::new(command_buffer);
+        let frames_in_flight = self.config.frames_in_flight;
+        let previous_frame = (self.current_frame + frames_in_flight - 1) % frames_in_flight;
+        let previous_frame_end = match self.frame_fences[previous_frame].clone() {
+            Some(fence) => Box::new(fence) as Box<dyn GpuFuture + Send + Sync>,
+            None => Box::new(sync::now(self.device.clone())),
+        };
+        let acquire_future = self.acquire_future.take().ok_or(Error::NotAcquired)?;
+        let future = previous_frame_end
+            .join(acquire_future)
+            .then_execute(graphical_queue.clone(), command_buffer)?
             .then_swapchain_present(
                 present_queue.clone(),
                 self.swapchain.clone(),
                 self.current_image_index,
-            )
-            .then_signal_fence_and_flush();
+            );
+        let future =
+            (Box::new(future) as Box<dyn GpuFuture + Send + Sync>).then_signal_fence_and_flush();
 
-        match future {
-            Ok(future) => {
-                self.previous_frame_end = Some(future.boxed());
-            }
+        self.frame_fences[self.current_frame] = match future {
+            Ok(future) => Some(Arc::new(future)),
             Err(FlushError::OutOfDate) => {
                 self.recreate_swapchain = true;
-                self.previous_frame_end = Some(sync::now(self.device.clone()).boxed());
-            }
-            Err(e) => {
-                println!("Failed to flush future: {:?}", e);
-                self.previous_frame_end = Some(sync::now(self.device.clone()).boxed());
+                None
             }
-        }
-    }
-
-    pub fn inner(&self) -> &DynamicState {
-        &self.resizehelper.inner()
+            Err(e) => return Err(e.into()),
+        };
+        self.current_frame = (self.current_frame + 1) % frames_in_flight;
+        Ok(())
     }
 }
@@ -0,0 +1,26 @@
+use vulkano::instance::PhysicalDevice;
+
+/// Sample counts we know how to render with, highest first.
+const SAMPLE_COUNTS: [u32; 4] = [8, 4, 2, 1];
+
+/// Picks the highest sample count not above `requested` that the device
+/// supports for both color and depth attachments.
+pub fn get_sample_count(physical: PhysicalDevice, requested: u32) -> u32 {
+    let limits = physical.limits();
+    let supported =
+        limits.framebuffer_color_sample_counts() & limits.framebuffer_depth_sample_counts();
+    let samples = SAMPLE_COUNTS
+        .iter()
+        .copied()
+        .filter(|&count| count <= requested)
+        .find(|&count| supported & count != 0)
+        .unwrap_or(1);
+    if samples != requested {
+        log::warn!(
+            "msaa: {} samples not supported, using {} instead",
+            requested,
+            samples
+        );
+    }
+    samples
+}
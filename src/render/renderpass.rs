@@ -1,39 +1,67 @@
-use std::sync::Arc;
+use vulkano::format::{ClearValue, Format, FormatTy};
 
-use vulkano::device::Device;
-use vulkano::format::Format;
-use vulkano::render_pass::RenderPass;
-use vulkano::swapchain::Swapchain;
+use super::graph::{AttachmentInfo, AttachmentSize, PassDesc, RenderGraph, BACKBUFFER};
 
-use winit::window::Window;
+/// Pass drawing the scene, the one `App::render` records.
+pub const SCENE_PASS: &str = "scene";
+pub const DEPTH: &str = "depth";
+/// Multisampled color resolved into the backbuffer.
+pub const SCENE_COLOR: &str = "scene color";
+/// Pass drawing over the finished image, see `add_overlay_pass`.
+pub const OVERLAY_PASS: &str = "overlay";
 
-pub fn get_render_pass(
-    device: &Arc<Device>,
-    swapchain: &Arc<Swapchain<Window>>,
-) -> Arc<RenderPass> {
-    let render_pass = Arc::new(
-        vulkano::single_pass_renderpass!(
-            device.clone(),
-            attachments: {
-                color: {
-                    load: Clear,
-                    store: Store,
-                    format: swapchain.format(),
-                    samples: 1,
-                },
-                depth: {
-                    load: Clear,
-                    store: DontCare,
-                    format: Format::D16Unorm,
-                    samples: 1,
-                }
-            },
-            pass: {
-                color: [color],
-                depth_stencil: {depth}
-            }
-        )
-        .unwrap(),
+/// Creates the graph with the main pass. With more than one sample the scene
+/// is drawn into multisampled attachments and resolved into the swapchain
+/// image; the pipeline picks up the sample count from the subpass.
+pub fn get_default_graph(
+    surface_format: Format,
+    samples: u32,
+    depth_format: Format,
+    reverse_z: bool,
+) -> RenderGraph {
+    let depth = if reverse_z { 0.0 } else { 1.0 };
+    let depth = match depth_format.ty() {
+        FormatTy::DepthStencil => ClearValue::DepthStencil((depth, 0)),
+        _ => ClearValue::Depth(depth),
+    };
+    let mut graph = RenderGraph::default();
+    graph.add_attachment(
+        DEPTH,
+        AttachmentInfo {
+            format: depth_format,
+            samples,
+            size: AttachmentSize::Swapchain,
+            clear: depth,
+        },
     );
-    render_pass
+    let mut scene = PassDesc {
+        depth: Some(DEPTH),
+        ..PassDesc::new(SCENE_PASS)
+    };
+    if samples > 1 {
+        graph.add_attachment(
+            SCENE_COLOR,
+            AttachmentInfo {
+                format: surface_format,
+                samples,
+                size: AttachmentSize::Swapchain,
+                clear: [0.0, 0.0, 0.0, 1.0].into(),
+            },
+        );
+        scene.color = vec![SCENE_COLOR];
+        scene.resolve = vec![BACKBUFFER];
+    } else {
+        scene.color = vec![BACKBUFFER];
+    }
+    graph.add_pass(scene);
+    graph
+}
+
+/// Appends a pass drawing over the swapchain image once every pass already in
+/// the graph wrote it, so it stays out of post-processing.
+pub fn add_overlay_pass(graph: &mut RenderGraph) {
+    graph.add_pass(PassDesc {
+        color: vec![BACKBUFFER],
+        ..PassDesc::new(OVERLAY_PASS)
+    });
 }
@@ -1,25 +1,192 @@
 use std::sync::Arc;
 
+use vulkano::device::DeviceOwned;
+use vulkano::format::Format;
 use vulkano::image::{ImageUsage, SwapchainImage};
-use vulkano::swapchain::{Surface, Swapchain};
+use vulkano::swapchain::{Capabilities, ColorSpace, PresentMode, Surface, Swapchain};
 use vulkano::sync::SharingMode;
 
 use winit::window::Window;
 
-use crate::device::LogicalDevice;
+use crate::device::{set_debug_name, LogicalDevice};
+use crate::error::{Error, Result};
+
+pub type SwapchainAndImages = (Arc<Swapchain<Window>>, Vec<Arc<SwapchainImage<Window>>>);
+
+/// How frames are handed to the display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PresentModeConfig {
+    /// Wait for vertical blank, never tears.
+    Vsync,
+    /// Like `Vsync`, but tears instead of waiting when a frame is late.
+    Adaptive,
+    /// Replace the queued frame with the newest one, never tears.
+    Mailbox,
+    /// Present right away, may tear.
+    Immediate,
+}
+
+impl PresentModeConfig {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Vsync => Self::Adaptive,
+            Self::Adaptive => Self::Mailbox,
+            Self::Mailbox => Self::Immediate,
+            Self::Immediate => Self::Vsync,
+        }
+    }
+
+    /// Modes to try in order, ending with fifo which is always supported.
+    fn fallbacks(self) -> &'static [PresentMode] {
+        match self {
+            Self::Vsync => &[PresentMode::Fifo],
+            Self::Adaptive => &[PresentMode::Relaxed, PresentMode::Fifo],
+            Self::Mailbox => &[PresentMode::Mailbox, PresentMode::Fifo],
+            Self::Immediate => &[
+                PresentMode::Immediate,
+                PresentMode::Mailbox,
+                PresentMode::Fifo,
+            ],
+        }
+    }
+}
+
+/// Wide gamut output, used only when the surface supports it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HdrMode {
+    /// 10 bit BT.2020 with the ST 2084 (PQ) transfer function.
+    Hdr10,
+    /// 16 bit float linear extended sRGB.
+    ScRgb,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SwapchainConfig {
+    pub present_mode: PresentModeConfig,
+    /// Requested number of swapchain images, clamped to the surface
+    /// capabilities; `None` uses one more than the minimum.
+    pub image_count: Option<u32>,
+    /// Only applied when the swapchain is first created.
+    pub hdr: Option<HdrMode>,
+}
+
+impl Default for SwapchainConfig {
+    fn default() -> Self {
+        Self {
+            present_mode: PresentModeConfig::Vsync,
+            image_count: None,
+            hdr: None,
+        }
+    }
+}
+
+/// Encoding shaders must apply to linear color before writing it to the
+/// swapchain image, matches `output_transfer` in the shaders.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputTransfer {
+    /// Written as is, the format either encodes sRGB itself or is linear.
+    Linear = 0,
+    /// Gamma encode in the shader, for UNORM formats in the sRGB color space.
+    Srgb = 1,
+    /// ST 2084 (PQ) encode in the shader, for HDR10.
+    Pq = 2,
+}
+
+/// Format and color space of the swapchain images.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SurfaceFormat {
+    pub format: Format,
+    pub color_space: ColorSpace,
+}
+
+impl SurfaceFormat {
+    pub fn is_srgb(&self) -> bool {
+        matches!(
+            self.format,
+            Format::B8G8R8A8Srgb
+                | Format::R8G8B8A8Srgb
+                | Format::A8B8G8R8SrgbPack32
+                | Format::B8G8R8Srgb
+                | Format::R8G8B8Srgb
+        )
+    }
+
+    pub fn get_transfer(&self) -> OutputTransfer {
+        match self.color_space {
+            ColorSpace::Hdr10St2084 => OutputTransfer::Pq,
+            ColorSpace::SrgbNonLinear if !self.is_srgb() => OutputTransfer::Srgb,
+            _ => OutputTransfer::Linear,
+        }
+    }
+}
+
+/// SDR formats in order of preference, hardware sRGB encoding first.
+const SDR_FORMATS: [(Format, ColorSpace); 4] = [
+    (Format::B8G8R8A8Srgb, ColorSpace::SrgbNonLinear),
+    (Format::R8G8B8A8Srgb, ColorSpace::SrgbNonLinear),
+    (Format::B8G8R8A8Unorm, ColorSpace::SrgbNonLinear),
+    (Format::R8G8B8A8Unorm, ColorSpace::SrgbNonLinear),
+];
+
+fn get_surface_format(caps: &Capabilities, config: &SwapchainConfig) -> Result<SurfaceFormat> {
+    let hdr = match config.hdr {
+        Some(HdrMode::Hdr10) => Some((Format::A2B10G10R10UnormPack32, ColorSpace::Hdr10St2084)),
+        Some(HdrMode::ScRgb) => Some((Format::R16G16B16A16Sfloat, ColorSpace::ExtendedSrgbLinear)),
+        None => None,
+    };
+    let (format, color_space) = hdr
+        .iter()
+        .chain(SDR_FORMATS.iter())
+        .find(|preferred| caps.supported_formats.contains(preferred))
+        .or_else(|| caps.supported_formats.first())
+        .copied()
+        .ok_or(Error::Unsupported("surface format"))?;
+    Ok(SurfaceFormat {
+        format,
+        color_space,
+    })
+}
+
+fn get_present_mode(caps: &Capabilities, config: &SwapchainConfig) -> PresentMode {
+    let supported = caps.present_modes;
+    config
+        .present_mode
+        .fallbacks()
+        .iter()
+        .copied()
+        .find(|mode| match mode {
+            PresentMode::Immediate => supported.immediate,
+            PresentMode::Mailbox => supported.mailbox,
+            PresentMode::Fifo => supported.fifo,
+            PresentMode::Relaxed => supported.relaxed,
+        })
+        .unwrap_or(PresentMode::Fifo)
+}
+
+fn get_image_count(caps: &Capabilities, config: &SwapchainConfig) -> u32 {
+    let requested = config.image_count.unwrap_or(caps.min_image_count + 1);
+    let count = requested.max(caps.min_image_count);
+    match caps.max_image_count {
+        Some(max) => count.min(max),
+        None => count,
+    }
+}
 
 pub fn get_swapchain(
     surface: &Arc<Surface<Window>>,
     logical_device: &LogicalDevice,
-) -> (Arc<Swapchain<Window>>, Vec<Arc<SwapchainImage<Window>>>) {
-    let caps = surface
-        .capabilities(logical_device.device.physical_device())
-        .unwrap();
+    config: &SwapchainConfig,
+) -> Result<(SwapchainAndImages, SurfaceFormat)> {
+    let caps = surface.capabilities(logical_device.device.physical_device())?;
     let graphical_queue = &logical_device.graphical_queue.clone();
     let present_queue = &logical_device.present_queue.clone();
 
-    let alpha = caps.supported_composite_alpha.iter().next().unwrap();
-    let format = caps.supported_formats[0].0;
+    let alpha = caps
+        .supported_composite_alpha
+        .iter()
+        .next()
+        .ok_or(Error::Unsupported("composite alpha mode"))?;
+    let surface_format = get_surface_format(&caps, config)?;
     let dim = surface.window().inner_size().into();
 
     let sharing: SharingMode = if graphical_queue != present_queue {
@@ -28,14 +195,35 @@ pub fn get_swapchain(
         graphical_queue.into()
     };
 
-    Swapchain::start(logical_device.device.clone(), surface.clone())
-        .num_images(caps.min_image_count)
-        .format(format)
+    let swapchain = Swapchain::start(logical_device.device.clone(), surface.clone())
+        .num_images(get_image_count(&caps, config))
+        .format(surface_format.format)
+        .color_space(surface_format.color_space)
         .dimensions(dim)
         .usage(ImageUsage::color_attachment())
         .sharing_mode(sharing)
         .composite_alpha(alpha)
-        .present_mode(vulkano::swapchain::PresentMode::Fifo)
-        .build()
-        .expect("failed to create swapchain")
+        .present_mode(get_present_mode(&caps, config))
+        .build()?;
+    set_debug_name(&logical_device.device, &*swapchain.0, "swapchain");
+    Ok((swapchain, surface_format))
+}
+
+/// Rebuilds the swapchain for the current window size and `config`, keeping
+/// the other creation parameters.
+pub fn recreate_swapchain(
+    swapchain: &Arc<Swapchain<Window>>,
+    config: &SwapchainConfig,
+) -> Result<SwapchainAndImages> {
+    let surface = swapchain.surface();
+    let caps = surface.capabilities(swapchain.device().physical_device())?;
+    let dim: [u32; 2] = surface.window().inner_size().into();
+    let recreated = swapchain
+        .recreate()
+        .dimensions(dim)
+        .num_images(get_image_count(&caps, config))
+        .present_mode(get_present_mode(&caps, config))
+        .build()?;
+    set_debug_name(swapchain.device(), &*recreated.0, "swapchain");
+    Ok(recreated)
 }
@@ -6,15 +6,25 @@ pub mod vs {
 
               layout(location = 0) in vec3 position;
               layout(location = 1) in vec3 color;
+              layout(location = 2) in vec4 model_x;
+              layout(location = 3) in vec4 model_y;
+              layout(location = 4) in vec4 model_z;
+              layout(location = 5) in vec4 model_w;
+              layout(location = 6) in vec4 tint;
+              layout(location = 7) in ivec2 custom;
               layout(location = 0) out vec4 vertex_color;
+              layout(location = 1) flat out uint output_transfer;
 
               layout(push_constant) uniform PushConstantData {
-                  mat4 transform;
+                  mat4 view_proj;
+                  uint output_transfer;
               } push;
 
               void main() {
-                  gl_Position = push.transform * vec4(position, 1.0);
-                  vertex_color = vec4(color, 1.0);
+                  mat4 model = mat4(model_x, model_y, model_z, model_w);
+                  gl_Position = push.view_proj * model * vec4(position, 1.0);
+                  vertex_color = vec4(color * tint.rgb, tint.a);
+                  output_transfer = push.output_transfer;
               }
           "
     }
@@ -23,13 +33,500 @@ pub mod vs {
 pub mod fs {
     vulkano_shaders::shader! {
         ty: "fragment",
+        include: ["src/shaders"],
         src: "
                 #version 450
 
-                layout(location =0) in vec4 vertex_color;
-                layout(location =0) out vec4 f_color;
+                layout(location = 0) in vec4 vertex_color;
+                layout(location = 1) flat in uint output_transfer;
+                layout(location = 0) out vec4 f_color;
+
+                #include <transfer.glsl>
+
+                void main() {
+                    vec3 color = vertex_color.rgb;
+                    color = encode_output(color, output_transfer);
+                    f_color = vec4(color, vertex_color.a);
+                }
+            "
+    }
+}
+
+/// Triangle covering the screen, drawn without vertex buffers by the
+/// post-processing passes.
+pub mod fullscreen_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+              #version 450
+
+              layout(location = 0) out vec2 uv;
+
+              void main() {
+                  int index = int(gl_VertexIndex);
+                  uv = vec2(float((index << 1) & 2), float(index & 2));
+                  gl_Position = vec4(uv * 2.0 - 1.0, 0.0, 1.0);
+              }
+          "
+    }
+}
+
+/// Writes the last post-processing target to the swapchain image with the
+/// output transfer applied.
+pub mod blit_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        include: ["src/shaders"],
+        src: "
+                #version 450
+
+                #include <post.glsl>
+
+                #include <transfer.glsl>
+
+                void main() {
+                    vec3 color = max(texture(sampler2D(source, source_sampler), uv).rgb, vec3(0.0));
+                    color = encode_output(color, push.output_transfer);
+                    f_color = vec4(color, 1.0);
+                }
+            "
+    }
+}
+
+/// ACES filmic curve, `params.x` is the exposure.
+pub mod tonemap_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        include: ["src/shaders"],
+        src: "
+                #version 450
+
+                #include <post.glsl>
+
+                void main() {
+                    vec3 x = texture(sampler2D(source, source_sampler), uv).rgb * push.params.x;
+                    vec3 color = (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14);
+                    f_color = vec4(clamp(color, 0.0, 1.0), 1.0);
+                }
+            "
+    }
+}
+
+/// Power curve, `params.x` is the gamma.
+pub mod gamma_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        include: ["src/shaders"],
+        src: "
+                #version 450
+
+                #include <post.glsl>
+
+                void main() {
+                    vec3 color = max(texture(sampler2D(source, source_sampler), uv).rgb, vec3(0.0));
+                    f_color = vec4(pow(color, vec3(1.0 / push.params.x)), 1.0);
+                }
+            "
+    }
+}
+
+/// Darkens the corners, `params.x` is the intensity and `params.y` the
+/// distance from the center where darkening starts.
+pub mod vignette_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        include: ["src/shaders"],
+        src: "
+                #version 450
+
+                #include <post.glsl>
+
+                void main() {
+                    vec3 color = texture(sampler2D(source, source_sampler), uv).rgb;
+                    float falloff = smoothstep(push.params.y, push.params.y + 0.5, distance(uv, vec2(0.5)));
+                    f_color = vec4(color * (1.0 - push.params.x * falloff), 1.0);
+                }
+            "
+    }
+}
+
+/// FXAA on luma edges, `params.x` is the longest search span in texels.
+pub mod fxaa_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        include: ["src/shaders"],
+        src: "
+                #version 450
+
+                #include <post.glsl>
+
+                const float REDUCE_MIN = 1.0 / 128.0;
+                const float REDUCE_MUL = 1.0 / 8.0;
+
+                vec3 fetch(vec2 offset) {
+                    return texture(sampler2D(source, source_sampler), uv + offset).rgb;
+                }
+
+                float luma(vec3 color) {
+                    return dot(color, vec3(0.299, 0.587, 0.114));
+                }
+
+                void main() {
+                    vec2 texel = push.texel_size;
+                    float luma_nw = luma(fetch(vec2(-1.0, -1.0) * texel));
+                    float luma_ne = luma(fetch(vec2(1.0, -1.0) * texel));
+                    float luma_sw = luma(fetch(vec2(-1.0, 1.0) * texel));
+                    float luma_se = luma(fetch(vec2(1.0, 1.0) * texel));
+                    float luma_m = luma(fetch(vec2(0.0)));
+                    float luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+                    float luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+                    vec2 dir = vec2(
+                        (luma_sw + luma_se) - (luma_nw + luma_ne),
+                        (luma_nw + luma_sw) - (luma_ne + luma_se)
+                    );
+                    float reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+                    float scale = 1.0 / (min(abs(dir.x), abs(dir.y)) + reduce);
+                    dir = clamp(dir * scale, vec2(-push.params.x), vec2(push.params.x)) * texel;
+
+                    vec3 near = 0.5 * (fetch(dir * (1.0 / 3.0 - 0.5)) + fetch(dir * (2.0 / 3.0 - 0.5)));
+                    vec3 far = near * 0.5 + 0.25 * (fetch(dir * -0.5) + fetch(dir * 0.5));
+                    float luma_far = luma(far);
+                    if (luma_far < luma_min || luma_far > luma_max) {
+                        f_color = vec4(near, 1.0);
+                    } else {
+                        f_color = vec4(far, 1.0);
+                    }
+                }
+            "
+    }
+}
+
+/// Looks colors up in a 3D LUT, `params.x` blends between the input and the
+/// graded color and `params.y` is the LUT size.
+pub mod grading_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        include: ["src/shaders"],
+        src: "
+                #version 450
+
+                #include <post.glsl>
+
+                layout(set = 0, binding = 2) uniform texture3D lut;
+
+                void main() {
+                    vec3 color = clamp(texture(sampler2D(source, source_sampler), uv).rgb, 0.0, 1.0);
+                    // NOTE: sample texel centers so 0 and 1 map to the edge texels
+                    float size = push.params.y;
+                    vec3 coord = color * ((size - 1.0) / size) + 0.5 / size;
+                    vec3 graded = texture(sampler3D(lut, source_sampler), coord).rgb;
+                    f_color = vec4(mix(color, graded, push.params.x), 1.0);
+                }
+            "
+    }
+}
+
+/// Depth only pass drawing shadow casters from the light.
+pub mod shadow_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+              #version 450
+
+              layout(location = 0) in vec3 position;
+              layout(location = 2) in vec4 model_x;
+              layout(location = 3) in vec4 model_y;
+              layout(location = 4) in vec4 model_z;
+              layout(location = 5) in vec4 model_w;
+
+              layout(push_constant) uniform PushConstantData {
+                  mat4 view_proj;
+              } push;
+
+              void main() {
+                  mat4 model = mat4(model_x, model_y, model_z, model_w);
+                  gl_Position = push.view_proj * model * vec4(position, 1.0);
+              }
+          "
+    }
+}
+
+pub mod shadow_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+                #version 450
+
+                void main() {
+                }
+            "
+    }
+}
+
+/// `vs` with the world position and view depth the lit shader needs.
+pub mod lit_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+              #version 450
+
+              layout(location = 0) in vec3 position;
+              layout(location = 1) in vec3 color;
+              layout(location = 2) in vec4 model_x;
+              layout(location = 3) in vec4 model_y;
+              layout(location = 4) in vec4 model_z;
+              layout(location = 5) in vec4 model_w;
+              layout(location = 6) in vec4 tint;
+              layout(location = 7) in ivec2 custom;
+              layout(location = 0) out vec4 vertex_color;
+              layout(location = 1) flat out uint output_transfer;
+              layout(location = 2) out vec3 world_position;
+              layout(location = 3) out float view_depth;
+
+              layout(push_constant) uniform PushConstantData {
+                  mat4 view_proj;
+                  uint output_transfer;
+              } push;
+
+              void main() {
+                  mat4 model = mat4(model_x, model_y, model_z, model_w);
+                  vec4 world = model * vec4(position, 1.0);
+                  gl_Position = push.view_proj * world;
+                  vertex_color = vec4(color * tint.rgb, tint.a);
+                  output_transfer = push.output_transfer;
+                  world_position = world.xyz;
+                  view_depth = gl_Position.w;
+              }
+          "
+    }
+}
+
+/// `fs` lit by the directional light and shadowed by its cascades.
+pub mod lit_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        include: ["src/shaders"],
+        src: "
+                #version 450
+
+                layout(location = 0) in vec4 vertex_color;
+                layout(location = 1) flat in uint output_transfer;
+                layout(location = 2) in vec3 world_position;
+                layout(location = 3) in float view_depth;
+                layout(location = 0) out vec4 f_color;
+
+                // NOTE: light is (direction, ambient), bias is (depth, normal),
+                // filter is (texel size, pcf radius, cascade count)
+                layout(set = 0, binding = 0) uniform ShadowData {
+                    mat4 light_view_proj[4];
+                    vec4 splits;
+                    vec4 light;
+                    vec4 bias;
+                    vec4 filter;
+                } shadow;
+                layout(set = 0, binding = 1) uniform texture2D shadow_map_0;
+                layout(set = 0, binding = 2) uniform texture2D shadow_map_1;
+                layout(set = 0, binding = 3) uniform texture2D shadow_map_2;
+                layout(set = 0, binding = 4) uniform texture2D shadow_map_3;
+                layout(set = 0, binding = 5) uniform sampler shadow_sampler;
+
+                #include <transfer.glsl>
+
+                float sample_depth(int cascade, vec2 uv) {
+                    if (cascade == 0) {
+                        return texture(sampler2D(shadow_map_0, shadow_sampler), uv).r;
+                    } else if (cascade == 1) {
+                        return texture(sampler2D(shadow_map_1, shadow_sampler), uv).r;
+                    } else if (cascade == 2) {
+                        return texture(sampler2D(shadow_map_2, shadow_sampler), uv).r;
+                    }
+                    return texture(sampler2D(shadow_map_3, shadow_sampler), uv).r;
+                }
+
+                float get_visibility(vec3 normal) {
+                    int count = int(shadow.filter.z);
+                    int cascade = 0;
+                    while (cascade < count - 1 && view_depth > shadow.splits[cascade]) {
+                        cascade += 1;
+                    }
+                    vec3 position = world_position + normal * shadow.bias.y;
+                    vec4 light_position = shadow.light_view_proj[cascade] * vec4(position, 1.0);
+                    vec3 coord = light_position.xyz / light_position.w;
+                    vec2 uv = coord.xy * 0.5 + 0.5;
+                    if (coord.z > 1.0 || uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+                        return 1.0;
+                    }
+                    int radius = int(shadow.filter.y);
+                    float lit = 0.0;
+                    float taps = 0.0;
+                    for (int x = -radius; x <= radius; x++) {
+                        for (int y = -radius; y <= radius; y++) {
+                            vec2 offset = vec2(float(x), float(y)) * shadow.filter.x;
+                            if (coord.z - shadow.bias.x <= sample_depth(cascade, uv + offset)) {
+                                lit += 1.0;
+                            }
+                            taps += 1.0;
+                        }
+                    }
+                    return lit / taps;
+                }
+
+                void main() {
+                    // NOTE: screen y points down, so this faces the camera
+                    vec3 normal = normalize(cross(dFdy(world_position), dFdx(world_position)));
+                    float diffuse = max(dot(normal, -shadow.light.xyz), 0.0);
+                    if (diffuse > 0.0) {
+                        diffuse *= get_visibility(normal);
+                    }
+                    float ambient = shadow.light.w;
+                    vec3 color = vertex_color.rgb * (ambient + (1.0 - ambient) * diffuse);
+                    color = encode_output(color, output_transfer);
+                    f_color = vec4(color, vertex_color.a);
+                }
+            "
+    }
+}
+
+/// Colored lines queued through `debug_draw`.
+pub mod debug_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+              #version 450
+
+              layout(location = 0) in vec3 position;
+              layout(location = 1) in vec4 color;
+              layout(location = 0) out vec4 vertex_color;
+              layout(location = 1) flat out uint output_transfer;
+
+              layout(push_constant) uniform PushConstantData {
+                  mat4 view_proj;
+                  uint output_transfer;
+              } push;
+
+              void main() {
+                  gl_Position = push.view_proj * vec4(position, 1.0);
+                  vertex_color = color;
+                  output_transfer = push.output_transfer;
+              }
+          "
+    }
+}
+
+/// Glyph quads in window pixels, queued through `text`.
+pub mod text_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+              #version 450
+
+              layout(location = 0) in vec2 position;
+              layout(location = 1) in vec2 uv;
+              layout(location = 2) in vec4 color;
+              layout(location = 0) out vec2 glyph_uv;
+              layout(location = 1) out vec4 glyph_color;
+              layout(location = 2) flat out uint output_transfer;
+
+              layout(push_constant) uniform PushConstantData {
+                  vec2 screen_size;
+                  uint output_transfer;
+              } push;
+
+              void main() {
+                  gl_Position = vec4(position / push.screen_size * 2.0 - 1.0, 0.0, 1.0);
+                  glyph_uv = uv;
+                  glyph_color = color;
+                  output_transfer = push.output_transfer;
+              }
+          "
+    }
+}
+
+/// Glyph coverage from the single channel atlas.
+pub mod text_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        include: ["src/shaders"],
+        src: "
+                #version 450
+
+                layout(location = 0) in vec2 glyph_uv;
+                layout(location = 1) in vec4 glyph_color;
+                layout(location = 2) flat in uint output_transfer;
+                layout(location = 0) out vec4 f_color;
+
+                layout(set = 0, binding = 0) uniform texture2D atlas;
+                layout(set = 0, binding = 1) uniform sampler atlas_sampler;
+
+                #include <transfer.glsl>
+
+                void main() {
+                    float coverage = texture(sampler2D(atlas, atlas_sampler), glyph_uv).r;
+                    vec3 color = glyph_color.rgb;
+                    color = encode_output(color, output_transfer);
+                    f_color = vec4(color, glyph_color.a * coverage);
+                }
+            "
+    }
+}
+
+/// Immediate mode UI meshes in points, colors are premultiplied sRGB.
+pub mod gui_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        include: ["src/shaders"],
+        src: "
+              #version 450
+
+              layout(location = 0) in vec2 position;
+              layout(location = 1) in vec2 uv;
+              layout(location = 2) in vec4 color;
+              layout(location = 0) out vec2 gui_uv;
+              layout(location = 1) out vec4 gui_color;
+              layout(location = 2) flat out uint output_transfer;
+
+              layout(push_constant) uniform PushConstantData {
+                  vec2 screen_size;
+                  uint output_transfer;
+              } push;
+
+              #include <transfer.glsl>
+
+              void main() {
+                  gl_Position = vec4(position / push.screen_size * 2.0 - 1.0, 0.0, 1.0);
+                  gui_uv = uv;
+                  gui_color = vec4(srgb_decode(color.rgb), color.a);
+                  output_transfer = push.output_transfer;
+              }
+          "
+    }
+}
+
+/// Textured UI meshes, blended with premultiplied alpha.
+pub mod gui_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        include: ["src/shaders"],
+        src: "
+                #version 450
+
+                layout(location = 0) in vec2 gui_uv;
+                layout(location = 1) in vec4 gui_color;
+                layout(location = 2) flat in uint output_transfer;
+                layout(location = 0) out vec4 f_color;
+
+                layout(set = 0, binding = 0) uniform texture2D gui_texture;
+                layout(set = 0, binding = 1) uniform sampler gui_sampler;
+
+                #include <transfer.glsl>
+
                 void main() {
-                    f_color = vertex_color;
+                    vec4 color = gui_color * texture(sampler2D(gui_texture, gui_sampler), gui_uv);
+                    // NOTE: encode the straight color, the blend expects it premultiplied
+                    vec3 straight = color.rgb / max(color.a, 0.0001);
+                    straight = encode_output(straight, output_transfer);
+                    f_color = vec4(straight * color.a, color.a);
                 }
             "
     }
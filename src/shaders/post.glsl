@@ -0,0 +1,13 @@
+// Inputs and push constants shared by the post-processing passes.
+
+layout(location = 0) in vec2 uv;
+layout(location = 0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D source;
+layout(set = 0, binding = 1) uniform sampler source_sampler;
+
+layout(push_constant) uniform Params {
+    vec4 params;
+    vec2 texel_size;
+    uint output_transfer;
+} push;
@@ -0,0 +1,31 @@
+// sRGB and PQ transfer functions, `transfer` is an `OutputTransfer` value.
+
+vec3 srgb_encode(vec3 color) {
+    vec3 high = 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055;
+    return mix(color * 12.92, high, step(vec3(0.0031308), color));
+}
+
+vec3 srgb_decode(vec3 color) {
+    vec3 high = pow((color + 0.055) / 1.055, vec3(2.4));
+    return mix(color / 12.92, high, step(vec3(0.04045), color));
+}
+
+vec3 pq_encode(vec3 color) {
+    const mat3 bt709_to_bt2020 = mat3(
+        0.6274, 0.0691, 0.0164,
+        0.3293, 0.9195, 0.0880,
+        0.0433, 0.0114, 0.8956
+    );
+    // NOTE: scene white is mapped to 80 nits out of 10000
+    vec3 y = pow(bt709_to_bt2020 * color * (80.0 / 10000.0), vec3(0.1593017578125));
+    return pow((0.8359375 + 18.8515625 * y) / (1.0 + 18.6875 * y), vec3(78.84375));
+}
+
+vec3 encode_output(vec3 color, uint transfer) {
+    if (transfer == 1u) {
+        return srgb_encode(color);
+    } else if (transfer == 2u) {
+        return pq_encode(color);
+    }
+    return color;
+}
@@ -0,0 +1,323 @@
+use std::sync::Arc;
+
+use glam::{Mat4, Vec3};
+use vulkano::{
+    buffer::CpuBufferPool,
+    command_buffer::{AutoCommandBufferBuilder, DynamicState, PrimaryAutoCommandBuffer},
+    descriptor::{
+        descriptor_set::{PersistentDescriptorSet, UnsafeDescriptorSetLayout},
+        DescriptorSet,
+    },
+    device::Device,
+    format::{ClearValue, Format},
+    sampler::{Filter, MipmapMode, Sampler, SamplerAddressMode},
+};
+
+use crate::camera::Camera;
+use crate::device::PoolName;
+use crate::error::{Error, Result};
+use crate::light::DirectionalLight;
+use crate::model::{Frustum, GameObject, InstanceData};
+use crate::render::graph::{AttachmentInfo, AttachmentSize, PassDesc};
+use crate::render::{PassContext, Render, RenderGraph, SCENE_PASS};
+use crate::shaders::lit_fs::ty::ShadowData;
+use crate::simple_display::CullStats;
+
+mod pipeline;
+
+/// Cascade passes nearest first, each also names the shadow map it writes.
+const SHADOW_PASSES: [&str; 4] = [
+    "shadow cascade 0",
+    "shadow cascade 1",
+    "shadow cascade 2",
+    "shadow cascade 3",
+];
+pub const MAX_CASCADES: usize = SHADOW_PASSES.len();
+
+#[derive(Clone, Copy, Debug)]
+pub struct ShadowConfig {
+    /// Width and height of every cascade's shadow map.
+    pub resolution: u32,
+    /// Subtracted from the fragment's light space depth before comparing.
+    pub depth_bias: f32,
+    /// World units the lookup is moved along the surface normal.
+    pub normal_bias: f32,
+    /// PCF taps `2 * pcf_radius + 1` texels wide, 0 for a single tap.
+    pub pcf_radius: u32,
+    /// Number of shadow maps splitting the view, 1 to `MAX_CASCADES`.
+    pub cascades: usize,
+    /// Blend between uniform (0) and logarithmic (1) cascade splits.
+    pub split_lambda: f32,
+}
+
+impl Default for ShadowConfig {
+    fn default() -> Self {
+        Self {
+            resolution: 2048,
+            depth_bias: 0.002,
+            normal_bias: 0.02,
+            pcf_radius: 1,
+            cascades: 1,
+            split_lambda: 0.75,
+        }
+    }
+}
+
+impl ShadowConfig {
+    fn get_cascade_count(&self) -> usize {
+        self.cascades.clamp(1, MAX_CASCADES)
+    }
+}
+
+/// Index of the cascade drawn by `pass`, if it is a shadow pass.
+pub fn get_cascade_index(pass: &str) -> Option<usize> {
+    SHADOW_PASSES.iter().position(|&name| name == pass)
+}
+
+/// Adds a depth only pass per cascade and makes the scene pass sample them.
+pub fn add_passes(graph: &mut RenderGraph, config: &ShadowConfig, format: Format) -> Result<()> {
+    let cascades = &SHADOW_PASSES[..config.get_cascade_count()];
+    let scene = graph
+        .get_pass_mut(SCENE_PASS)
+        .ok_or_else(|| Error::RenderGraph(format!("no {} pass", SCENE_PASS)))?;
+    scene.reads.extend(cascades.iter().copied());
+    for &name in cascades {
+        graph.add_attachment(
+            name,
+            AttachmentInfo {
+                format,
+                samples: 1,
+                size: AttachmentSize::Absolute([config.resolution, config.resolution]),
+                clear: ClearValue::Depth(1.0),
+            },
+        );
+        graph.add_pass(PassDesc {
+            depth: Some(name),
+            ..PassDesc::new(name)
+        });
+    }
+    Ok(())
+}
+
+/// Light space of one cascade.
+#[derive(Clone, Copy, Debug)]
+pub struct Cascade {
+    pub view_proj: Mat4,
+    /// View depth where the cascade ends.
+    pub split: f32,
+}
+
+/// Draws shadow casters into the cascades and binds them for lit pipelines.
+pub struct ShadowMapper {
+    pipeline: Arc<pipeline::ShadowPipeline>,
+    instance_pool: CpuBufferPool<InstanceData>,
+    instance_name: PoolName,
+    uniform_pool: CpuBufferPool<ShadowData>,
+    uniform_name: PoolName,
+    sampler: Arc<Sampler>,
+    config: ShadowConfig,
+}
+
+impl ShadowMapper {
+    /// `render` must use a graph `add_passes` was called on with `config`.
+    pub fn new(device: &Arc<Device>, render: &Render, config: ShadowConfig) -> Result<Self> {
+        let render_pass = render
+            .get_render_pass(SHADOW_PASSES[0])
+            .ok_or_else(|| Error::RenderGraph(format!("no live {} pass", SHADOW_PASSES[0])))?;
+        let sampler = Sampler::new(
+            device.clone(),
+            Filter::Nearest,
+            Filter::Nearest,
+            MipmapMode::Nearest,
+            SamplerAddressMode::ClampToEdge,
+            SamplerAddressMode::ClampToEdge,
+            SamplerAddressMode::ClampToEdge,
+            0.0,
+            1.0,
+            0.0,
+            0.0,
+        )?;
+        Ok(Self {
+            pipeline: pipeline::get_pipeline(device, render_pass)?,
+            instance_pool: CpuBufferPool::vertex_buffer(device.clone()),
+            instance_name: PoolName::new("shadow caster instance data"),
+            uniform_pool: CpuBufferPool::uniform_buffer(device.clone()),
+            uniform_name: PoolName::new("shadow uniforms"),
+            sampler,
+            config,
+        })
+    }
+
+    pub fn get_config(&self) -> ShadowConfig {
+        self.config
+    }
+
+    /// Applies bias, filter and split changes from the next frame on.
+    /// `resolution` and `cascades` size the graph's passes and are kept.
+    pub fn set_config(&mut self, config: ShadowConfig) {
+        self.config = ShadowConfig {
+            resolution: self.config.resolution,
+            cascades: self.config.cascades,
+            ..config
+        };
+    }
+
+    /// Splits the camera's view up to its far plane and fits an orthographic
+    /// light projection around each part. Projections move in whole texels
+    /// so shadow edges do not shimmer as the camera moves.
+    pub fn get_cascades(
+        &self,
+        camera: &Camera,
+        aspect: f32,
+        light: &DirectionalLight,
+    ) -> Vec<Cascade> {
+        let count = self.config.get_cascade_count();
+        let direction = light.get_direction();
+        let up = if direction.y.abs() > 0.99 {
+            Vec3::Z
+        } else {
+            Vec3::Y
+        };
+        let light_view = Mat4::look_at_lh(Vec3::ZERO, direction, up);
+        let (near, far) = (camera.near, camera.far);
+        let tan_y = (camera.fov_y * 0.5).tan();
+        // NOTE: the camera has no view matrix, view space is world space
+        let mut start = near;
+        (1..=count)
+            .map(|index| {
+                let part = index as f32 / count as f32;
+                let log = near * (far / near).powf(part);
+                let uniform = near + (far - near) * part;
+                let end =
+                    self.config.split_lambda * log + (1.0 - self.config.split_lambda) * uniform;
+                let corners: Vec<Vec3> = [start, end]
+                    .iter()
+                    .flat_map(|&depth| {
+                        let (x, y) = (depth * tan_y * aspect, depth * tan_y);
+                        vec![
+                            Vec3::new(-x, -y, depth),
+                            Vec3::new(x, -y, depth),
+                            Vec3::new(-x, y, depth),
+                            Vec3::new(x, y, depth),
+                        ]
+                    })
+                    .collect();
+                start = end;
+                let center = corners.iter().fold(Vec3::ZERO, |sum, &c| sum + c) / 8.0;
+                let radius = corners
+                    .iter()
+                    .map(|corner| corner.distance(center))
+                    .fold(0.0, f32::max)
+                    .ceil();
+                let texel = 2.0 * radius / self.config.resolution as f32;
+                let mut center = light_view.transform_point3(center);
+                center.x = (center.x / texel).floor() * texel;
+                center.y = (center.y / texel).floor() * texel;
+                // NOTE: casters between the light and the view still cast
+                let proj = Mat4::orthographic_lh(
+                    center.x - radius,
+                    center.x + radius,
+                    center.y - radius,
+                    center.y + radius,
+                    center.z - radius - far,
+                    center.z + radius,
+                );
+                Cascade {
+                    view_proj: proj * light_view,
+                    split: end,
+                }
+            })
+            .collect()
+    }
+
+    /// Draws the objects casting shadows that can reach the cascade.
+    pub fn render_casters(
+        &self,
+        cmd_builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        game_objs: &[GameObject],
+        dynamicstate: &DynamicState,
+        view_proj: &Mat4,
+    ) -> Result<CullStats> {
+        let push_data = crate::shaders::shadow_vs::ty::PushConstantData {
+            view_proj: view_proj.to_cols_array_2d(),
+        };
+        let frustum = Frustum::from_matrix(view_proj);
+        let mut stats = CullStats::default();
+        let mut batches = std::collections::BTreeMap::new();
+        for obj in game_objs.iter().filter(|obj| obj.cast_shadows) {
+            let (aabb, sphere) = obj.get_world_bounds();
+            if !(frustum.intersects_sphere(&sphere) && frustum.intersects_aabb(&aabb)) {
+                stats.culled += 1;
+                continue;
+            }
+            stats.drawn += 1;
+            batches
+                .entry(obj.mesh.get_id())
+                .or_insert_with(|| (obj.mesh.clone(), vec![]))
+                .1
+                .push(obj.get_instance_data());
+        }
+        for (mesh, instances) in batches.into_values() {
+            let instance_buffer = self.instance_pool.chunk(instances)?;
+            self.instance_name.apply(&instance_buffer);
+            cmd_builder.draw(
+                self.pipeline.clone(),
+                dynamicstate,
+                (mesh.vertex_buffer.clone(), instance_buffer),
+                (),
+                push_data,
+                vec![],
+            )?;
+        }
+        Ok(stats)
+    }
+
+    /// Set 0 of the lit pipeline, recorded inside the scene pass after the
+    /// cascades were drawn.
+    pub fn get_scene_set(
+        &self,
+        layout: &Arc<UnsafeDescriptorSetLayout>,
+        pass: &PassContext,
+        cascades: &[Cascade],
+        light: &DirectionalLight,
+    ) -> Result<Arc<dyn DescriptorSet + Send + Sync>> {
+        let mut light_view_proj = [[[0.0; 4]; 4]; MAX_CASCADES];
+        let mut splits = [0.0; MAX_CASCADES];
+        for (index, cascade) in cascades.iter().enumerate() {
+            light_view_proj[index] = cascade.view_proj.to_cols_array_2d();
+            splits[index] = cascade.split;
+        }
+        let direction = light.get_direction();
+        let data = self.uniform_pool.next(ShadowData {
+            light_view_proj,
+            splits,
+            light: [direction.x, direction.y, direction.z, light.ambient],
+            bias: [self.config.depth_bias, self.config.normal_bias, 0.0, 0.0],
+            filter: [
+                1.0 / self.config.resolution as f32,
+                self.config.pcf_radius as f32,
+                cascades.len() as f32,
+                0.0,
+            ],
+        })?;
+        self.uniform_name.apply(&data);
+        // NOTE: unused bindings repeat the last cascade
+        let maps = (0..MAX_CASCADES)
+            .map(|index| {
+                let name = SHADOW_PASSES[index.min(cascades.len().max(1) - 1)];
+                pass.get_attachment(name)
+                    .cloned()
+                    .ok_or_else(|| Error::RenderGraph(format!("{} was not created", name)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let set = PersistentDescriptorSet::start(layout.clone())
+            .add_buffer(data)?
+            .add_image(maps[0].clone())?
+            .add_image(maps[1].clone())?
+            .add_image(maps[2].clone())?
+            .add_image(maps[3].clone())?
+            .add_sampler(self.sampler.clone())?
+            .build()?;
+        Ok(Arc::new(set))
+    }
+}
@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+use vulkano::descriptor::PipelineLayoutAbstract;
+use vulkano::device::Device;
+use vulkano::pipeline::depth_stencil::DepthStencil;
+use vulkano::pipeline::{vertex::OneVertexOneInstanceDefinition, GraphicsPipeline};
+use vulkano::render_pass::RenderPass;
+
+use crate::device::set_debug_name;
+use crate::error::Result;
+use crate::model::{InstanceData, Vertex};
+use crate::render::graph::get_subpass;
+use crate::shaders::{shadow_fs, shadow_vs};
+
+pub type ShadowPipeline = GraphicsPipeline<
+    OneVertexOneInstanceDefinition<Vertex, InstanceData>,
+    Box<dyn PipelineLayoutAbstract + Send + Sync + 'static>,
+>;
+
+/// Depth only pipeline, shadow maps always use a regular `0..1` depth range.
+pub fn get_pipeline(
+    device: &Arc<Device>,
+    renderpass: &Arc<RenderPass>,
+) -> Result<Arc<ShadowPipeline>> {
+    let vs = shadow_vs::Shader::load(device.clone())?;
+    let fs = shadow_fs::Shader::load(device.clone())?;
+
+    let pipeline = Arc::new(
+        GraphicsPipeline::start()
+            .vertex_input(OneVertexOneInstanceDefinition::<Vertex, InstanceData>::new())
+            .vertex_shader(vs.main_entry_point(), ())
+            .triangle_list()
+            .viewports_dynamic_scissors_irrelevant(1)
+            .fragment_shader(fs.main_entry_point(), ())
+            .depth_stencil(DepthStencil::simple_depth_test())
+            .render_pass(get_subpass(renderpass)?)
+            .build(device.clone())?,
+    );
+    set_debug_name(device, &*pipeline, "shadow pipeline");
+    Ok(pipeline)
+}
@@ -1,44 +1,196 @@
-use std::sync::Arc;
+use std::{collections::HashMap, ffi::CStr, sync::Arc};
 
 use vulkano::{
+    buffer::CpuBufferPool,
     command_buffer::{AutoCommandBufferBuilder, DynamicState, PrimaryAutoCommandBuffer},
+    descriptor::{
+        descriptor_set::UnsafeDescriptorSetLayout, DescriptorSet, PipelineLayoutAbstract,
+    },
     device::Device,
     render_pass::RenderPass,
 };
 
+use crate::device::{debug_utils_enabled, PoolName};
+use crate::error::Result;
+use crate::model::{BlendMode, Frustum, GameObject, InstanceData, Mesh};
+use crate::render::OutputTransfer;
+
 mod pipeline;
 
+/// Number of objects submitted and skipped by frustum culling in one frame.
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub struct CullStats {
+    pub drawn: usize,
+    pub culled: usize,
+}
+
+/// Consecutive objects drawn with one instanced draw call.
+struct Batch {
+    key: (usize, usize),
+    mesh: Arc<Mesh>,
+    blend: BlendMode,
+    instances: Vec<InstanceData>,
+}
+
 pub struct Pipeline {
-    pipeline: Arc<pipeline::ConcreteGraphicsPipeline>,
+    pipelines: HashMap<BlendMode, Arc<pipeline::ConcreteGraphicsPipeline>>,
+    instance_pool: CpuBufferPool<InstanceData>,
+    instance_name: PoolName,
+    output_transfer: OutputTransfer,
+    /// Wrap draws in command buffer labels, needs `VK_EXT_debug_utils`.
+    debug_labels: bool,
 }
 
+/// Command buffer labels need a static nul terminated name.
+fn label(name: &'static [u8]) -> &'static CStr {
+    CStr::from_bytes_with_nul(name).expect("label must be nul terminated")
+}
+
+const PASS_COLOR: [f32; 4] = [0.2, 0.6, 1.0, 1.0];
+const BATCH_COLOR: [f32; 4] = [1.0, 0.8, 0.2, 1.0];
+
 impl Pipeline {
-    pub fn new(device: &Arc<Device>, renderpass: &Arc<RenderPass>) -> Self {
-        let pipeline = pipeline::get_pipeline(&device, &renderpass);
-        Self { pipeline }
+    pub fn new(
+        device: &Arc<Device>,
+        renderpass: &Arc<RenderPass>,
+        reverse_z: bool,
+        output_transfer: OutputTransfer,
+        lit: bool,
+    ) -> Result<Self> {
+        let pipelines = BlendMode::ALL
+            .iter()
+            .map(|&blend| {
+                let pipeline = pipeline::get_pipeline(device, renderpass, reverse_z, lit, blend)?;
+                Ok((blend, pipeline))
+            })
+            .collect::<Result<_>>()?;
+        let instance_pool = CpuBufferPool::vertex_buffer(device.clone());
+        Ok(Self {
+            pipelines,
+            instance_pool,
+            instance_name: PoolName::new("instance data"),
+            output_transfer,
+            debug_labels: debug_utils_enabled(device),
+        })
+    }
+
+    /// Layout of the light and shadow map set, only lit pipelines have one.
+    pub fn get_shadow_layout(&self) -> Option<&Arc<UnsafeDescriptorSetLayout>> {
+        self.pipelines[&BlendMode::Opaque].descriptor_set_layout(0)
+    }
+
+    /// Groups opaque objects sharing a mesh and material so each group is
+    /// drawn with one instanced draw call, groups and the instances in them
+    /// stay in the order of `game_objs`.
+    fn batch_opaque(game_objs: &[&GameObject]) -> Vec<Batch> {
+        let mut batches: Vec<Batch> = vec![];
+        let mut indices = HashMap::new();
+        for obj in game_objs {
+            let index = *indices.entry(obj.get_batch_key()).or_insert_with(|| {
+                batches.push(Self::new_batch(obj));
+                batches.len() - 1
+            });
+            batches[index].instances.push(obj.get_instance_data());
+        }
+        batches
+    }
+
+    /// Only merges neighbours, blending depends on the draw order.
+    fn batch_in_order(game_objs: &[&GameObject]) -> Vec<Batch> {
+        let mut batches: Vec<Batch> = vec![];
+        for obj in game_objs {
+            match batches.last_mut() {
+                Some(batch) if batch.key == obj.get_batch_key() => {}
+                _ => batches.push(Self::new_batch(obj)),
+            }
+            if let Some(batch) = batches.last_mut() {
+                batch.instances.push(obj.get_instance_data());
+            }
+        }
+        batches
+    }
+
+    fn new_batch(obj: &GameObject) -> Batch {
+        Batch {
+            key: obj.get_batch_key(),
+            mesh: obj.mesh.clone(),
+            blend: obj.material.get_blend(),
+            instances: vec![],
+        }
     }
 
     pub fn render_game_objects(
         &self,
         cmd_builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
-        game_objs: &mut [crate::model::GameObject],
+        game_objs: &[GameObject],
         dynamicstate: &DynamicState,
         camera: &glam::Mat4,
-    ) {
-        for objs in game_objs.iter_mut() {
-            objs.rotate[0] = objs.rotate[0] + 0.01;
-            objs.rotate[1] = objs.rotate[1] + 0.01;
-            objs.rotate[2] = objs.rotate[2] + 0.01;
-            cmd_builder
-                .draw(
-                    self.pipeline.clone(),
-                    &dynamicstate,
-                    objs.vertex_buffer.clone(),
+        shadow_set: Option<&Arc<dyn DescriptorSet + Send + Sync>>,
+    ) -> Result<CullStats> {
+        let push_data = crate::shaders::vs::ty::PushConstantData {
+            view_proj: camera.to_cols_array_2d(),
+            output_transfer: self.output_transfer as u32,
+        };
+        let frustum = Frustum::from_matrix(camera);
+        let mut visible: Vec<_> = game_objs
+            .iter()
+            .filter_map(|obj| {
+                let (aabb, sphere) = obj.get_world_bounds();
+                if !(frustum.intersects_sphere(&sphere) && frustum.intersects_aabb(&aabb)) {
+                    return None;
+                }
+                // NOTE: clip w is the distance along the view direction
+                Some((obj, (*camera * sphere.center.extend(1.0)).w))
+            })
+            .collect();
+        let stats = CullStats {
+            drawn: visible.len(),
+            culled: game_objs.len() - visible.len(),
+        };
+        visible.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        let (opaque, mut transparent): (Vec<_>, Vec<_>) = visible
+            .into_iter()
+            .map(|(obj, _)| obj)
+            .partition(|obj| !obj.material.get_blend().is_transparent());
+        transparent.reverse();
+        let mut batches = Self::batch_opaque(&opaque);
+        batches.extend(Self::batch_in_order(&transparent));
+        if self.debug_labels {
+            cmd_builder.debug_marker_begin(label(b"game objects\0"), PASS_COLOR)?;
+        }
+        for batch in batches {
+            let pipeline = &self.pipelines[&batch.blend];
+            let instance_buffer = self.instance_pool.chunk(batch.instances)?;
+            self.instance_name.apply(&instance_buffer);
+            if self.debug_labels {
+                cmd_builder.debug_marker_begin(label(b"mesh batch\0"), BATCH_COLOR)?;
+            }
+            let buffers = (batch.mesh.vertex_buffer.clone(), instance_buffer);
+            match shadow_set {
+                Some(set) => cmd_builder.draw(
+                    pipeline.clone(),
+                    dynamicstate,
+                    buffers,
+                    set.clone(),
+                    push_data,
+                    vec![],
+                )?,
+                None => cmd_builder.draw(
+                    pipeline.clone(),
+                    dynamicstate,
+                    buffers,
                     (),
-                    objs.get_push_data(camera),
+                    push_data,
                     vec![],
-                )
-                .unwrap();
+                )?,
+            };
+            if self.debug_labels {
+                cmd_builder.debug_marker_end()?;
+            }
+        }
+        if self.debug_labels {
+            cmd_builder.debug_marker_end()?;
         }
+        Ok(stats)
     }
 }
@@ -2,35 +2,83 @@ use std::sync::Arc;
 
 use vulkano::descriptor::PipelineLayoutAbstract;
 use vulkano::device::Device;
-use vulkano::pipeline::{vertex::SingleBufferDefinition, GraphicsPipeline};
-use vulkano::render_pass::{RenderPass, Subpass};
+use vulkano::pipeline::blend::{AttachmentBlend, BlendFactor};
+use vulkano::pipeline::depth_stencil::{Compare, DepthStencil};
+use vulkano::pipeline::{vertex::OneVertexOneInstanceDefinition, GraphicsPipeline};
+use vulkano::render_pass::RenderPass;
 
-use crate::shaders::{fs, vs};
-use crate::model::Vertex;
+use crate::device::set_debug_name;
+use crate::error::Result;
+use crate::model::{BlendMode, InstanceData, Vertex};
+use crate::render::graph::get_subpass;
+use crate::shaders::{fs, lit_fs, lit_vs, vs};
 
 pub type ConcreteGraphicsPipeline = GraphicsPipeline<
-    SingleBufferDefinition<Vertex>,
+    OneVertexOneInstanceDefinition<Vertex, InstanceData>,
     Box<dyn PipelineLayoutAbstract + Send + Sync + 'static>,
 >;
 pub fn get_pipeline(
     device: &Arc<Device>,
     renderpass: &Arc<RenderPass>,
-) -> Arc<ConcreteGraphicsPipeline> {
-    let vs = vs::Shader::load(device.clone()).unwrap();
-    let fs = fs::Shader::load(device.clone()).unwrap();
-
-    let pipeline = Arc::new(
-        GraphicsPipeline::start()
-            .vertex_input_single_buffer()
-            .vertex_shader(vs.main_entry_point(), ())
-            // .polygon_mode_line()
-            .triangle_list()
-            .viewports_dynamic_scissors_irrelevant(1)
-            .fragment_shader(fs.main_entry_point(), ())
-            .depth_stencil_simple_depth()
-            .render_pass(Subpass::from(renderpass.clone(), 0).unwrap())
-            .build(device.clone())
-            .unwrap(),
+    reverse_z: bool,
+    lit: bool,
+    blend_mode: BlendMode,
+) -> Result<Arc<ConcreteGraphicsPipeline>> {
+    let depth_stencil = if reverse_z {
+        DepthStencil {
+            depth_compare: Compare::Greater,
+            ..DepthStencil::simple_depth_test()
+        }
+    } else {
+        DepthStencil::simple_depth_test()
+    };
+    // NOTE: transparent objects are tested against depth but do not hide
+    // what is drawn behind them later
+    let depth_stencil = DepthStencil {
+        depth_write: !blend_mode.is_transparent(),
+        ..depth_stencil
+    };
+    let blend = match blend_mode {
+        BlendMode::Opaque => AttachmentBlend::pass_through(),
+        BlendMode::Alpha => AttachmentBlend::alpha_blending(),
+        BlendMode::Additive => AttachmentBlend {
+            color_destination: BlendFactor::One,
+            alpha_destination: BlendFactor::One,
+            ..AttachmentBlend::alpha_blending()
+        },
+        BlendMode::Premultiplied => AttachmentBlend {
+            color_source: BlendFactor::One,
+            alpha_source: BlendFactor::One,
+            ..AttachmentBlend::alpha_blending()
+        },
+    };
+    // NOTE: every shader module has its own entry point type
+    macro_rules! build {
+        ($vs:ident, $fs:ident) => {{
+            let vs = $vs::Shader::load(device.clone())?;
+            let fs = $fs::Shader::load(device.clone())?;
+            GraphicsPipeline::start()
+                .vertex_input(OneVertexOneInstanceDefinition::<Vertex, InstanceData>::new())
+                .vertex_shader(vs.main_entry_point(), ())
+                // .polygon_mode_line()
+                .triangle_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(fs.main_entry_point(), ())
+                .depth_stencil(depth_stencil)
+                .blend_collective(blend)
+                .render_pass(get_subpass(renderpass)?)
+                .build(device.clone())?
+        }};
+    }
+    let pipeline = Arc::new(if lit {
+        build!(lit_vs, lit_fs)
+    } else {
+        build!(vs, fs)
+    });
+    set_debug_name(
+        device,
+        &*pipeline,
+        &format!("simple display {:?} pipeline", blend_mode),
     );
-    pipeline
+    Ok(pipeline)
 }
@@ -0,0 +1,213 @@
+use std::{collections::HashMap, sync::Arc};
+
+use ab_glyph::{point, Font as _, FontArc, GlyphId, PxScale, ScaleFont};
+use glam::Vec2;
+use vulkano::{
+    format::Format,
+    image::{view::ImageView, ImageAccess, ImageDimensions, ImmutableImage, MipmapsCount},
+    sync::GpuFuture,
+};
+
+use super::Align;
+use crate::device::{set_debug_name, LogicalDevice};
+use crate::error::Result;
+
+pub type AtlasView = Arc<ImageView<Arc<ImmutableImage>>>;
+
+/// Grown to the next power of two fitting the widest glyph.
+const MIN_ATLAS_WIDTH: u32 = 512;
+/// Empty texels around each glyph so linear filtering does not bleed.
+const PADDING: u32 = 1;
+
+#[derive(Clone, Copy)]
+struct Glyph {
+    id: GlyphId,
+    /// Top left of the bitmap relative to the pen on the baseline.
+    offset: Vec2,
+    size: Vec2,
+    uv_min: Vec2,
+    uv_max: Vec2,
+}
+
+/// A glyph's coverage before it is copied into the atlas.
+struct Bitmap {
+    c: char,
+    id: GlyphId,
+    offset: Vec2,
+    width: u32,
+    height: u32,
+    coverage: Vec<u8>,
+    x: u32,
+    y: u32,
+}
+
+/// Glyph bitmaps rasterized once at `pixel_size` and scaled when drawn.
+pub struct GlyphAtlas {
+    font: FontArc,
+    pixel_size: f32,
+    glyphs: HashMap<char, Glyph>,
+    /// Drawn for characters missing from the atlas.
+    fallback: Option<Glyph>,
+    pub view: AtlasView,
+}
+
+/// Glyph quad in window pixels.
+pub struct Quad {
+    pub min: Vec2,
+    pub max: Vec2,
+    pub uv_min: Vec2,
+    pub uv_max: Vec2,
+}
+
+impl GlyphAtlas {
+    /// Rasterizes the glyphs of `chars` the font has, packed in rows into a
+    /// single channel image.
+    pub fn new(
+        logical_device: &LogicalDevice,
+        font: &FontArc,
+        pixel_size: f32,
+        chars: impl IntoIterator<Item = char>,
+    ) -> Result<Self> {
+        let scale = PxScale::from(pixel_size);
+        let mut chars: Vec<char> = chars.into_iter().collect();
+        chars.sort_unstable();
+        chars.dedup();
+        let mut bitmaps = vec![];
+        for c in chars {
+            let id = font.glyph_id(c);
+            if id.0 == 0 {
+                continue;
+            }
+            let glyph = id.with_scale_and_position(scale, point(0.0, 0.0));
+            let mut bitmap = Bitmap {
+                c,
+                id,
+                offset: Vec2::ZERO,
+                width: 0,
+                height: 0,
+                coverage: vec![],
+                x: 0,
+                y: 0,
+            };
+            // NOTE: whitespace has an advance but no outline
+            if let Some(outlined) = font.outline_glyph(glyph) {
+                let bounds = outlined.px_bounds();
+                bitmap.offset = Vec2::new(bounds.min.x, bounds.min.y);
+                bitmap.width = bounds.width() as u32;
+                bitmap.height = bounds.height() as u32;
+                bitmap.coverage = vec![0; (bitmap.width * bitmap.height) as usize];
+                outlined.draw(|gx, gy, value| {
+                    let index = (gy * bitmap.width + gx) as usize;
+                    bitmap.coverage[index] = (value * 255.0).round() as u8;
+                });
+            }
+            bitmaps.push(bitmap);
+        }
+        let atlas_width = bitmaps
+            .iter()
+            .map(|bitmap| bitmap.width + 2 * PADDING)
+            .fold(MIN_ATLAS_WIDTH, u32::max)
+            .next_power_of_two();
+        let (mut x, mut y, mut row_height) = (PADDING, PADDING, 0);
+        for bitmap in &mut bitmaps {
+            if x + bitmap.width + PADDING > atlas_width {
+                x = PADDING;
+                y += row_height + PADDING;
+                row_height = 0;
+            }
+            bitmap.x = x;
+            bitmap.y = y;
+            x += bitmap.width + PADDING;
+            row_height = row_height.max(bitmap.height);
+        }
+        let height = (y + row_height + PADDING).next_power_of_two();
+        let atlas_size = Vec2::new(atlas_width as f32, height as f32);
+        let mut texels = vec![0u8; (atlas_width * height) as usize];
+        let mut glyphs = HashMap::new();
+        for bitmap in bitmaps {
+            let width = bitmap.width as usize;
+            for row in 0..bitmap.height {
+                let start = ((bitmap.y + row) * atlas_width + bitmap.x) as usize;
+                let source = row as usize * width;
+                texels[start..start + width]
+                    .copy_from_slice(&bitmap.coverage[source..source + width]);
+            }
+            let size = Vec2::new(bitmap.width as f32, bitmap.height as f32);
+            let uv_min = Vec2::new(bitmap.x as f32, bitmap.y as f32) / atlas_size;
+            glyphs.insert(
+                bitmap.c,
+                Glyph {
+                    id: bitmap.id,
+                    offset: bitmap.offset,
+                    size,
+                    uv_min,
+                    uv_max: uv_min + size / atlas_size,
+                },
+            );
+        }
+        let (image, upload) = ImmutableImage::from_iter(
+            texels.into_iter(),
+            ImageDimensions::Dim2d {
+                width: atlas_width,
+                height,
+                array_layers: 1,
+            },
+            MipmapsCount::One,
+            Format::R8Unorm,
+            logical_device.graphical_queue.clone(),
+        )?;
+        upload.then_signal_fence_and_flush()?.wait(None)?;
+        set_debug_name(&logical_device.device, image.inner().image, "glyph atlas");
+        Ok(Self {
+            font: font.clone(),
+            pixel_size,
+            fallback: glyphs.get(&'?').copied(),
+            glyphs,
+            view: ImageView::new(image)?,
+        })
+    }
+
+    fn get_glyph(&self, c: char) -> Option<Glyph> {
+        self.glyphs.get(&c).copied().or(self.fallback)
+    }
+
+    /// Lays out `text` at `size` pixels with the top of its first line at
+    /// `position`, every line aligned on `position.x`.
+    pub fn layout(&self, text: &str, position: Vec2, size: f32, align: Align) -> Vec<Quad> {
+        let font = self.font.as_scaled(PxScale::from(size));
+        let scale = size / self.pixel_size;
+        let line_height = font.height() + font.line_gap();
+        let mut quads = vec![];
+        for (index, line) in text.lines().enumerate() {
+            let glyphs: Vec<Glyph> = line.chars().filter_map(|c| self.get_glyph(c)).collect();
+            let mut pens = Vec::with_capacity(glyphs.len());
+            let mut pen = 0.0;
+            for (i, glyph) in glyphs.iter().enumerate() {
+                if i > 0 {
+                    pen += font.kern(glyphs[i - 1].id, glyph.id);
+                }
+                pens.push(pen);
+                pen += font.h_advance(glyph.id);
+            }
+            let start = match align {
+                Align::Left => 0.0,
+                Align::Center => -pen * 0.5,
+                Align::Right => -pen,
+            };
+            let baseline = position.y + font.ascent() + index as f32 * line_height;
+            for (glyph, pen) in glyphs.iter().zip(pens) {
+                if glyph.size.x == 0.0 {
+                    continue;
+                }
+                let min = Vec2::new(position.x + start + pen, baseline) + glyph.offset * scale;
+                quads.push(Quad {
+                    min,
+                    max: min + glyph.size * scale,
+                    uv_min: glyph.uv_min,
+                    uv_max: glyph.uv_max,
+                });
+            }
+        }
+        quads
+    }
+}
@@ -0,0 +1,259 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use ab_glyph::FontArc;
+use glam::{Mat4, Vec2, Vec3};
+use vulkano::{
+    buffer::CpuBufferPool,
+    descriptor::{descriptor_set::PersistentDescriptorSet, DescriptorSet, PipelineLayoutAbstract},
+    render_pass::RenderPass,
+    sampler::{Filter, MipmapMode, Sampler, SamplerAddressMode},
+};
+
+use crate::device::{LogicalDevice, PoolName};
+use crate::error::Result;
+use crate::render::{OutputTransfer, PassContext};
+
+mod atlas;
+mod pipeline;
+
+/// A TrueType or OpenType font, cheap to clone.
+#[derive(Clone, Debug)]
+pub struct Font(FontArc);
+
+impl Font {
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self> {
+        Ok(Self(FontArc::try_from_vec(data)?))
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+}
+
+/// Font and glyphs every view's text is drawn with.
+#[derive(Clone, Debug)]
+pub struct TextConfig {
+    pub font: Font,
+    /// Height glyphs are rasterized at, text far from it looks blurry.
+    pub pixel_size: f32,
+    /// Characters rasterized besides printable ASCII.
+    pub extra_chars: String,
+}
+
+impl TextConfig {
+    pub fn new(font: Font) -> Self {
+        Self {
+            font,
+            pixel_size: 32.0,
+            extra_chars: String::new(),
+        }
+    }
+}
+
+/// Where lines are placed relative to the text's position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextStyle {
+    pub color: [f32; 4],
+    /// Line height in logical pixels, like egui's points.
+    pub size: f32,
+    pub align: Align,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 1.0, 1.0, 1.0],
+            size: 16.0,
+            align: Align::Left,
+        }
+    }
+}
+
+enum Anchor {
+    /// Logical window pixels, origin at the top left.
+    Screen(Vec2),
+    World(Vec3),
+}
+
+struct Text {
+    text: String,
+    anchor: Anchor,
+    style: TextStyle,
+}
+
+/// Shared by every thread, like the queued debug lines.
+static TEXTS: Mutex<Vec<Text>> = Mutex::new(Vec::new());
+
+fn get_texts() -> MutexGuard<'static, Vec<Text>> {
+    TEXTS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn queue(text: &str, anchor: Anchor, style: TextStyle) {
+    get_texts().push(Text {
+        text: text.to_owned(),
+        anchor,
+        style,
+    });
+}
+
+/// Queues `text` with the top of its first line at `position` in logical
+/// window pixels, drawn over every view this frame.
+pub fn screen(text: &str, position: Vec2, style: TextStyle) {
+    queue(text, Anchor::Screen(position), style);
+}
+
+/// Queues `text` at the point of the window `position` is seen at, hidden
+/// when it is behind the camera.
+pub fn world(text: &str, position: Vec3, style: TextStyle) {
+    queue(text, Anchor::World(position), style);
+}
+
+/// Drops the queued text, called once all views were drawn.
+pub fn end_frame() {
+    get_texts().clear();
+}
+
+/// Corner of a glyph quad.
+#[derive(Default, Clone, Copy)]
+pub struct TextVertex {
+    position: [f32; 2],
+    uv: [f32; 2],
+    color: [f32; 4],
+}
+
+vulkano::impl_vertex!(TextVertex, position, uv, color);
+
+/// Draws the queued text in a pass over the finished image.
+pub struct TextRenderer {
+    pipeline: Arc<pipeline::TextPipeline>,
+    atlas: atlas::GlyphAtlas,
+    set: Arc<dyn DescriptorSet + Send + Sync>,
+    vertex_pool: CpuBufferPool<TextVertex>,
+    vertex_name: PoolName,
+    output_transfer: OutputTransfer,
+}
+
+impl TextRenderer {
+    pub fn new(
+        logical_device: &LogicalDevice,
+        renderpass: &Arc<RenderPass>,
+        config: &TextConfig,
+        output_transfer: OutputTransfer,
+    ) -> Result<Self> {
+        let device = &logical_device.device;
+        let chars = (' '..='~').chain(config.extra_chars.chars());
+        let atlas =
+            atlas::GlyphAtlas::new(logical_device, &config.font.0, config.pixel_size, chars)?;
+        let pipeline = pipeline::get_pipeline(device, renderpass)?;
+        let sampler = Sampler::new(
+            device.clone(),
+            Filter::Linear,
+            Filter::Linear,
+            MipmapMode::Nearest,
+            SamplerAddressMode::ClampToEdge,
+            SamplerAddressMode::ClampToEdge,
+            SamplerAddressMode::ClampToEdge,
+            0.0,
+            1.0,
+            0.0,
+            0.0,
+        )?;
+        let layout = pipeline
+            .descriptor_set_layout(0)
+            .expect("text shader uses set 0")
+            .clone();
+        let set = PersistentDescriptorSet::start(layout)
+            .add_image(atlas.view.clone())?
+            .add_sampler(sampler)?
+            .build()?;
+        Ok(Self {
+            pipeline,
+            atlas,
+            set: Arc::new(set),
+            vertex_pool: CpuBufferPool::vertex_buffer(device.clone()),
+            vertex_name: PoolName::new("text vertices"),
+            output_transfer,
+        })
+    }
+
+    /// Lays out the queued text, projecting world anchors with `view_proj`.
+    /// Screen anchors and sizes are scaled by `scale_factor` to the viewport's
+    /// physical pixels.
+    pub fn render(
+        &self,
+        pass: &mut PassContext,
+        view_proj: &Mat4,
+        scale_factor: f32,
+    ) -> Result<()> {
+        let screen_size = match &pass.dynamic_state.viewports {
+            Some(viewports) => Vec2::from(viewports[0].dimensions),
+            None => return Ok(()),
+        };
+        let mut vertices = vec![];
+        for text in get_texts().iter() {
+            let position = match text.anchor {
+                Anchor::Screen(position) => position * scale_factor,
+                Anchor::World(position) => {
+                    let clip = *view_proj * position.extend(1.0);
+                    if clip.w <= 0.0 {
+                        continue;
+                    }
+                    let ndc = Vec2::new(clip.x, clip.y) / clip.w;
+                    (ndc * 0.5 + Vec2::splat(0.5)) * screen_size
+                }
+            };
+            let style = &text.style;
+            let size = style.size * scale_factor;
+            let quads = self.atlas.layout(&text.text, position, size, style.align);
+            for quad in quads {
+                let corner = |x: bool, y: bool| TextVertex {
+                    position: [
+                        if x { quad.max.x } else { quad.min.x },
+                        if y { quad.max.y } else { quad.min.y },
+                    ],
+                    uv: [
+                        if x { quad.uv_max.x } else { quad.uv_min.x },
+                        if y { quad.uv_max.y } else { quad.uv_min.y },
+                    ],
+                    color: style.color,
+                };
+                vertices.extend_from_slice(&[
+                    corner(false, false),
+                    corner(true, false),
+                    corner(false, true),
+                    corner(false, true),
+                    corner(true, false),
+                    corner(true, true),
+                ]);
+            }
+        }
+        if vertices.is_empty() {
+            return Ok(());
+        }
+        let push_data = crate::shaders::text_vs::ty::PushConstantData {
+            screen_size: screen_size.into(),
+            output_transfer: self.output_transfer as u32,
+        };
+        let vertex_buffer = self.vertex_pool.chunk(vertices)?;
+        self.vertex_name.apply(&vertex_buffer);
+        pass.cmd_builder.draw(
+            self.pipeline.clone(),
+            &pass.dynamic_state,
+            vertex_buffer,
+            self.set.clone(),
+            push_data,
+            vec![],
+        )?;
+        Ok(())
+    }
+}
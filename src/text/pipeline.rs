@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use vulkano::descriptor::PipelineLayoutAbstract;
+use vulkano::device::Device;
+use vulkano::pipeline::blend::AttachmentBlend;
+use vulkano::pipeline::{vertex::SingleBufferDefinition, GraphicsPipeline};
+use vulkano::render_pass::RenderPass;
+
+use super::TextVertex;
+use crate::device::set_debug_name;
+use crate::error::Result;
+use crate::render::graph::get_subpass;
+use crate::shaders::{text_fs, text_vs};
+
+pub type TextPipeline = GraphicsPipeline<
+    SingleBufferDefinition<TextVertex>,
+    Box<dyn PipelineLayoutAbstract + Send + Sync + 'static>,
+>;
+
+pub fn get_pipeline(
+    device: &Arc<Device>,
+    renderpass: &Arc<RenderPass>,
+) -> Result<Arc<TextPipeline>> {
+    let vs = text_vs::Shader::load(device.clone())?;
+    let fs = text_fs::Shader::load(device.clone())?;
+
+    let pipeline = Arc::new(
+        GraphicsPipeline::start()
+            .vertex_input_single_buffer::<TextVertex>()
+            .vertex_shader(vs.main_entry_point(), ())
+            .triangle_list()
+            .viewports_dynamic_scissors_irrelevant(1)
+            .fragment_shader(fs.main_entry_point(), ())
+            .blend_collective(AttachmentBlend::alpha_blending())
+            .render_pass(get_subpass(renderpass)?)
+            .build(device.clone())?,
+    );
+    set_debug_name(device, &*pipeline, "text pipeline");
+    Ok(pipeline)
+}
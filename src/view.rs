@@ -0,0 +1,378 @@
+use std::{sync::Arc, time::Instant};
+
+use glam::Mat4;
+use vulkano::command_buffer::{AutoCommandBufferBuilder, DynamicState, PrimaryAutoCommandBuffer};
+use vulkano::descriptor::DescriptorSet;
+use vulkano::render_pass::RenderPass;
+use vulkano::swapchain::Surface;
+
+use winit::event::WindowEvent;
+use winit::window::{Window, WindowId};
+
+use crate::app::AppConfig;
+use crate::camera::Camera;
+use crate::debug_draw::DebugRenderer;
+use crate::device::LogicalDevice;
+use crate::error::{Error, Result};
+use crate::gui::{self, FrameStats, Gui};
+use crate::light::DirectionalLight;
+use crate::model::GameObject;
+use crate::postprocess::{self, PostChain};
+use crate::render::{
+    self, OutputTransfer, PassContext, Render, RenderGraph, OVERLAY_PASS, SCENE_PASS,
+};
+use crate::shadow::{self, ShadowMapper};
+use crate::simple_display::{self, CullStats};
+use crate::text::TextRenderer;
+
+/// One window with its own swapchain, render graph, pipeline and camera, all
+/// views share the device.
+pub struct View {
+    pub render: Render,
+    pub camera: Camera,
+    pub light: DirectionalLight,
+    window_id: WindowId,
+    simple_display: simple_display::Pipeline,
+    /// Effects between the HDR scene and the swapchain image, if any.
+    post_chain: Option<PostChain>,
+    shadow_mapper: Option<ShadowMapper>,
+    debug_renderer: DebugRenderer,
+    /// Draws queued text in the overlay pass when a font is configured.
+    text_renderer: Option<TextRenderer>,
+    /// Immediate mode UI drawn in the overlay pass, see `AppConfig::gui`.
+    gui: Option<Gui>,
+    stats: FrameStats,
+    last_frame: Option<Instant>,
+}
+
+enum FrameTarget<'a> {
+    Scene {
+        simple_display: &'a simple_display::Pipeline,
+        shadow_set: Option<Arc<dyn DescriptorSet + Send + Sync>>,
+    },
+    Shadow(&'a ShadowMapper),
+}
+
+/// A pass being recorded for one view, handed to `App::render` for the scene
+/// pass and every shadow cascade.
+pub struct Frame<'a> {
+    pub window_id: WindowId,
+    /// `SCENE_PASS` or a shadow cascade.
+    pub pass: &'static str,
+    pub cmd_builder: &'a mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    pub dynamic_state: &'a DynamicState,
+    /// The camera's, or the light's for a shadow cascade.
+    pub view_proj: Mat4,
+    target: FrameTarget<'a>,
+    /// Summed over the scene pass for `FrameStats`.
+    cull: CullStats,
+}
+
+impl Frame<'_> {
+    /// Draws `game_objects` with the view's built-in pipeline, or only their
+    /// depth in a shadow cascade.
+    pub fn draw_game_objects(&mut self, game_objects: &[GameObject]) -> Result<CullStats> {
+        match &self.target {
+            FrameTarget::Scene {
+                simple_display,
+                shadow_set,
+            } => {
+                let stats = simple_display.render_game_objects(
+                    self.cmd_builder,
+                    game_objects,
+                    self.dynamic_state,
+                    &self.view_proj,
+                    shadow_set.as_ref(),
+                )?;
+                self.cull.drawn += stats.drawn;
+                self.cull.culled += stats.culled;
+                Ok(stats)
+            }
+            FrameTarget::Shadow(shadow_mapper) => shadow_mapper.render_casters(
+                self.cmd_builder,
+                game_objects,
+                self.dynamic_state,
+                &self.view_proj,
+            ),
+        }
+    }
+
+    pub fn is_shadow_pass(&self) -> bool {
+        matches!(self.target, FrameTarget::Shadow(_))
+    }
+}
+
+impl View {
+    pub fn new(
+        logical_device: &LogicalDevice,
+        surface: &Arc<Surface<Window>>,
+        config: &AppConfig,
+        fov_y: f32,
+        near: f32,
+        far: f32,
+    ) -> Result<Self> {
+        // NOTE: the device was created against the first window only
+        if !surface.is_supported(logical_device.present_queue.family())? {
+            return Err(Error::MissingQueueFamily("present"));
+        }
+        let mut render = Render::new(logical_device, surface, config.render)?;
+        let post_effects = &config.post_effects;
+        let mut graph = render.get_render_graph().clone();
+        if let Some(shadows) = &config.shadows {
+            let physical = logical_device.device.physical_device();
+            shadow::add_passes(&mut graph, shadows, render::get_shadow_format(physical)?)?;
+        }
+        if !post_effects.is_empty() {
+            postprocess::add_passes(&mut graph, post_effects.len())?;
+        }
+        if config.text.is_some() || config.gui {
+            render::add_overlay_pass(&mut graph);
+        }
+        render.set_render_graph(graph)?;
+        let shadow_mapper = match config.shadows {
+            Some(shadows) => Some(ShadowMapper::new(&logical_device.device, &render, shadows)?),
+            None => None,
+        };
+        let post_chain = if post_effects.is_empty() {
+            None
+        } else {
+            Some(PostChain::new(logical_device, &render, post_effects)?)
+        };
+        // NOTE: with effects the scene stays linear until the blit
+        let output_transfer = match post_chain {
+            Some(_) => OutputTransfer::Linear,
+            None => render.get_surface_format().get_transfer(),
+        };
+        let scene_pass = get_scene_pass(&render)?;
+        let simple_display = simple_display::Pipeline::new(
+            &logical_device.device,
+            scene_pass,
+            render.is_reverse_z(),
+            output_transfer,
+            shadow_mapper.is_some(),
+        )?;
+        let debug_renderer = DebugRenderer::new(
+            &logical_device.device,
+            scene_pass,
+            render.is_reverse_z(),
+            output_transfer,
+        )?;
+        let get_overlay_pass = || {
+            render
+                .get_render_pass(OVERLAY_PASS)
+                .ok_or_else(|| Error::RenderGraph(format!("no live {} pass", OVERLAY_PASS)))
+        };
+        let surface_transfer = render.get_surface_format().get_transfer();
+        let text_renderer = match &config.text {
+            Some(text) => Some(TextRenderer::new(
+                logical_device,
+                get_overlay_pass()?,
+                text,
+                surface_transfer,
+            )?),
+            None => None,
+        };
+        let gui = if config.gui {
+            Some(Gui::new(
+                logical_device,
+                get_overlay_pass()?,
+                surface_transfer,
+            )?)
+        } else {
+            None
+        };
+        let camera = Camera::new(fov_y, near, far, render.is_reverse_z());
+        Ok(Self {
+            render,
+            camera,
+            light: config.light,
+            window_id: surface.window().id(),
+            simple_display,
+            post_chain,
+            shadow_mapper,
+            debug_renderer,
+            text_renderer,
+            gui,
+            stats: FrameStats::default(),
+            last_frame: None,
+        })
+    }
+
+    /// Replaces the view's render graph, which must keep a scene pass with
+    /// the attachment formats and sample count it was created with.
+    pub fn set_render_graph(&mut self, graph: RenderGraph) -> Result<()> {
+        self.render.set_render_graph(graph)?;
+        get_scene_pass(&self.render).map(|_| ())
+    }
+
+    pub fn get_window_id(&self) -> WindowId {
+        self.window_id
+    }
+
+    pub fn get_frame_stats(&self) -> FrameStats {
+        self.stats
+    }
+
+    pub fn get_gui(&self) -> Option<&Gui> {
+        self.gui.as_ref()
+    }
+
+    /// For changing effect settings at runtime, see `PostChain::set_effect`.
+    pub fn get_post_chain_mut(&mut self) -> Option<&mut PostChain> {
+        self.post_chain.as_mut()
+    }
+
+    /// Forwards `event` to the UI, if there is one.
+    pub fn on_window_event(&mut self, event: &WindowEvent) {
+        if let Some(gui) = &mut self.gui {
+            gui.on_event(event, self.render.get_scale_factor());
+        }
+    }
+
+    /// Runs a UI frame with a window of the view's built-in panels, then
+    /// `build` for the app's own. Does nothing without a UI.
+    pub fn run_gui(
+        &mut self,
+        logical_device: &LogicalDevice,
+        build: impl FnOnce(&egui::Context) -> Result<()>,
+    ) -> Result<()> {
+        let gui = match &mut self.gui {
+            Some(gui) => gui,
+            None => return Ok(()),
+        };
+        let (render, camera, light, shadow_mapper, post_chain, stats) = (
+            &mut self.render,
+            &mut self.camera,
+            &mut self.light,
+            &mut self.shadow_mapper,
+            &mut self.post_chain,
+            self.stats,
+        );
+        let dimensions = render.get_dimensions();
+        let scale_factor = render.get_scale_factor();
+        let mut result = Ok(());
+        gui.run(logical_device, dimensions, scale_factor, |ctx| {
+            egui::Window::new("View").show(ctx, |ui| {
+                gui::panels::frame_stats(ui, &stats);
+                ui.collapsing("Camera", |ui| gui::panels::camera(ui, camera));
+                ui.collapsing("Light", |ui| gui::panels::light(ui, light));
+                if let Some(shadow_mapper) = shadow_mapper {
+                    ui.collapsing("Shadows", |ui| gui::panels::shadows(ui, shadow_mapper));
+                }
+                if let Some(post_chain) = post_chain {
+                    ui.collapsing("Post effects", |ui| {
+                        gui::panels::post_effects(ui, post_chain)
+                    });
+                }
+                ui.collapsing("Renderer", |ui| gui::panels::renderer(ui, render));
+            });
+            result = build(ctx);
+        })?;
+        result
+    }
+
+    /// Records a frame, `record` is called for the scene pass and the shadow
+    /// cascades and `record_pass` for every other live pass of the graph,
+    /// then submits it. Lines queued through `debug_draw` are drawn after the
+    /// scene, text queued through `text` and the UI in the overlay pass.
+    /// Does nothing if no swapchain image is available.
+    pub fn draw(
+        &mut self,
+        logical_device: &LogicalDevice,
+        mut record: impl FnMut(&mut Frame) -> Result<()>,
+        mut record_pass: impl FnMut(&mut PassContext) -> Result<()>,
+    ) -> Result<()> {
+        let aspect = self.render.get_aspect_ratio();
+        let view_proj = self.camera.get_projection(aspect);
+        let light = self.light;
+        let cascades = match &self.shadow_mapper {
+            Some(shadow_mapper) => shadow_mapper.get_cascades(&self.camera, aspect, &light),
+            None => vec![],
+        };
+        let now = Instant::now();
+        if let Some(last_frame) = self.last_frame.replace(now) {
+            let frame_time = (now - last_frame).as_secs_f32();
+            self.stats.frame_time += (frame_time - self.stats.frame_time) * 0.1;
+        }
+        let mut scene_cull = CullStats::default();
+        let render = &mut self.render;
+        if let Some(mut cmd_builder) =
+            render.get_command_buffer_builder(logical_device.graphical_queue.clone())?
+        {
+            let window_id = self.window_id;
+            let simple_display = &self.simple_display;
+            let post_chain = &mut self.post_chain;
+            let shadow_mapper = &self.shadow_mapper;
+            let debug_renderer = &self.debug_renderer;
+            let text_renderer = &self.text_renderer;
+            let gui = &self.gui;
+            let scale_factor = render.get_scale_factor() as f32;
+            render.execute_graph(&mut cmd_builder, |pass| {
+                if let Some(post_chain) = post_chain {
+                    if post_chain.record(pass)? {
+                        return Ok(());
+                    }
+                }
+                let cascade = shadow::get_cascade_index(pass.name);
+                if let (Some(shadow_mapper), Some(index)) = (shadow_mapper, cascade) {
+                    return record(&mut Frame {
+                        window_id,
+                        pass: pass.name,
+                        cmd_builder: pass.cmd_builder,
+                        dynamic_state: &pass.dynamic_state,
+                        view_proj: cascades[index].view_proj,
+                        target: FrameTarget::Shadow(shadow_mapper),
+                        cull: CullStats::default(),
+                    });
+                }
+                if pass.name == OVERLAY_PASS {
+                    if let Some(text_renderer) = text_renderer {
+                        text_renderer.render(pass, &view_proj, scale_factor)?;
+                    }
+                    if let Some(gui) = gui {
+                        gui.render(pass)?;
+                    }
+                    return Ok(());
+                }
+                if pass.name != SCENE_PASS {
+                    return record_pass(pass);
+                }
+                let shadow_set = match (shadow_mapper, simple_display.get_shadow_layout()) {
+                    (Some(shadow_mapper), Some(layout)) => {
+                        Some(shadow_mapper.get_scene_set(layout, pass, &cascades, &light)?)
+                    }
+                    _ => None,
+                };
+                let mut frame = Frame {
+                    window_id,
+                    pass: pass.name,
+                    cmd_builder: pass.cmd_builder,
+                    dynamic_state: &pass.dynamic_state,
+                    view_proj,
+                    target: FrameTarget::Scene {
+                        simple_display,
+                        shadow_set,
+                    },
+                    cull: CullStats::default(),
+                };
+                record(&mut frame)?;
+                scene_cull = frame.cull;
+                debug_renderer.render(pass.cmd_builder, &pass.dynamic_state, &view_proj)
+            })?;
+            render.render(
+                cmd_builder,
+                &logical_device.graphical_queue.clone(),
+                &logical_device.present_queue.clone(),
+            )?;
+            logical_device.check_validation();
+            self.stats.cull = scene_cull;
+        }
+        Ok(())
+    }
+}
+
+fn get_scene_pass(render: &Render) -> Result<&Arc<RenderPass>> {
+    render
+        .get_render_pass(SCENE_PASS)
+        .ok_or_else(|| Error::RenderGraph(format!("no live {} pass", SCENE_PASS)))
+}
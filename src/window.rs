@@ -4,15 +4,123 @@ use vulkano::instance::Instance;
 use vulkano::swapchain::Surface;
 use vulkano_win::VkSurfaceBuild;
 
-use winit::event_loop::EventLoop;
+use winit::dpi::LogicalSize;
+use winit::event_loop::EventLoopWindowTarget;
+use winit::monitor::MonitorHandle;
 use winit::window::Window;
-use winit::window::WindowBuilder;
-
-pub fn init_window(instance: &Arc<Instance>) -> (EventLoop<()>, Arc<Surface<Window>>) {
-    let event_loop = EventLoop::new();
-    let surface = WindowBuilder::new()
-        .with_title(env!("CARGO_PKG_NAME"))
-        .build_vk_surface(&event_loop, instance.clone())
-        .expect("failed to crate window builder");
-    (event_loop, surface)
+use winit::window::{Fullscreen, WindowBuilder};
+
+use crate::error::Result;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FullscreenMode {
+    Windowed,
+    /// Window covering the monitor, keeps the desktop video mode.
+    Borderless,
+    /// Switches the monitor to its largest video mode.
+    Exclusive,
+}
+
+#[derive(Clone, Debug)]
+pub struct WindowConfig {
+    pub title: String,
+    /// Initial inner size in logical pixels, `None` leaves it to the platform.
+    pub size: Option<[u32; 2]>,
+    pub min_size: Option<[u32; 2]>,
+    pub max_size: Option<[u32; 2]>,
+    pub resizable: bool,
+    pub fullscreen: FullscreenMode,
+    /// Index into the available monitors, `None` uses the primary one.
+    pub monitor: Option<usize>,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            title: env!("CARGO_PKG_NAME").to_owned(),
+            size: None,
+            min_size: None,
+            max_size: None,
+            resizable: true,
+            fullscreen: FullscreenMode::Windowed,
+            monitor: None,
+        }
+    }
+}
+
+impl WindowConfig {
+    /// Reads `HEX_FULLSCREEN` (`borderless` or `exclusive`) and `HEX_MONITOR`
+    /// (an index).
+    pub fn from_env() -> Self {
+        let fullscreen = match std::env::var("HEX_FULLSCREEN").as_deref() {
+            Ok("borderless") => FullscreenMode::Borderless,
+            Ok("exclusive") => FullscreenMode::Exclusive,
+            _ => FullscreenMode::Windowed,
+        };
+        Self {
+            fullscreen,
+            monitor: std::env::var("HEX_MONITOR")
+                .ok()
+                .and_then(|value| value.parse().ok()),
+            ..Self::default()
+        }
+    }
+
+    fn get_monitor(&self, event_loop: &EventLoopWindowTarget<()>) -> Option<MonitorHandle> {
+        self.monitor
+            .and_then(|index| event_loop.available_monitors().nth(index))
+            .or_else(|| event_loop.primary_monitor())
+    }
+
+    fn get_fullscreen(&self, monitor: Option<MonitorHandle>) -> Option<Fullscreen> {
+        match self.fullscreen {
+            FullscreenMode::Windowed => None,
+            FullscreenMode::Borderless => Some(Fullscreen::Borderless(monitor)),
+            FullscreenMode::Exclusive => {
+                let monitor = match monitor {
+                    Some(monitor) => monitor,
+                    None => {
+                        log::warn!("no monitor for exclusive fullscreen, using borderless");
+                        return Some(Fullscreen::Borderless(None));
+                    }
+                };
+                let mode = monitor.video_modes().max_by_key(|mode| {
+                    let size = mode.size();
+                    (size.width * size.height, mode.refresh_rate())
+                });
+                if mode.is_none() {
+                    log::warn!("no video mode for exclusive fullscreen, using borderless");
+                }
+                Some(mode.map_or(Fullscreen::Borderless(None), Fullscreen::Exclusive))
+            }
+        }
+    }
+}
+
+pub fn create_window(
+    event_loop: &EventLoopWindowTarget<()>,
+    instance: &Arc<Instance>,
+    config: &WindowConfig,
+) -> Result<Arc<Surface<Window>>> {
+    let monitor = config.get_monitor(event_loop);
+    let mut builder = WindowBuilder::new()
+        .with_title(config.title.clone())
+        .with_resizable(config.resizable)
+        .with_fullscreen(config.get_fullscreen(monitor.clone()));
+    if let Some([width, height]) = config.size {
+        builder = builder.with_inner_size(LogicalSize::new(width, height));
+    }
+    if let Some([width, height]) = config.min_size {
+        builder = builder.with_min_inner_size(LogicalSize::new(width, height));
+    }
+    if let Some([width, height]) = config.max_size {
+        builder = builder.with_max_inner_size(LogicalSize::new(width, height));
+    }
+    let surface = builder.build_vk_surface(event_loop, instance.clone())?;
+    if let (FullscreenMode::Windowed, Some(_), Some(monitor)) =
+        (config.fullscreen, config.monitor, monitor)
+    {
+        surface.window().set_outer_position(monitor.position());
+    }
+    Ok(surface)
 }